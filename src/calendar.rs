@@ -0,0 +1,231 @@
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use cursive::direction::Direction;
+use cursive::event::{Callback, Event, EventResult, Key};
+use cursive::theme::{ColorStyle, Effect, Style};
+use cursive::traits::*;
+use cursive::vec::Vec2;
+use cursive::view::{CannotFocus, View};
+use cursive::views::Panel;
+use cursive::{Cursive, Printer};
+
+use crate::state::AppState;
+use crate::taskwarrior;
+use crate::timesheet;
+
+const WIDTH: usize = 20;
+
+/// A month grid, Monday first, marking days with due tasks (bold) and
+/// days with tracked time (underlined).
+pub struct CalendarView {
+    month: NaiveDate,
+    cursor: NaiveDate,
+    due: BTreeSet<NaiveDate>,
+    tracked: BTreeSet<NaiveDate>
+}
+
+impl CalendarView {
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            month: first_of_month(today),
+            cursor: today,
+            due: BTreeSet::new(),
+            tracked: BTreeSet::new()
+        }
+    }
+
+    pub fn month(&self) -> NaiveDate {
+        self.month
+    }
+
+    pub fn set_marks(&mut self, due: BTreeSet<NaiveDate>, tracked: BTreeSet<NaiveDate>) {
+        self.due = due;
+        self.tracked = tracked;
+    }
+
+    /// Moves the cursor; the marks are reloaded when the month changes.
+    fn move_to(&mut self, day: NaiveDate) -> EventResult {
+        self.cursor = day;
+        let month = first_of_month(day);
+        if month == self.month {
+            return EventResult::Consumed(None);
+        }
+
+        self.month = month;
+        self.set_marks(BTreeSet::new(), BTreeSet::new());
+        EventResult::Consumed(Some(Callback::from_fn(|s| {
+            let result = refresh(s);
+            s.user_data::<AppState>().expect("App state").record_refresh("calendar", result);
+        })))
+    }
+}
+
+impl View for CalendarView {
+    fn draw(&self, printer: &Printer) {
+        let title = self.month.format("%B %Y").to_string();
+        printer.with_color(ColorStyle::title_primary(), |printer| {
+            printer.print((0, 0), &format!("{:^width$}", title, width = WIDTH));
+        });
+        printer.print((0, 1), "Mo Tu We Th Fr Sa Su");
+
+        for (row, week) in weeks(self.month).iter().enumerate() {
+            for (column, day) in week.iter().enumerate() {
+                let day = match day {
+                    Some(day) => *day,
+                    None => continue
+                };
+
+                let mut style = Style::from(ColorStyle::primary());
+                if self.due.contains(&day) {
+                    style = Style::from(ColorStyle::secondary()).combine(Effect::Bold);
+                }
+                if self.tracked.contains(&day) {
+                    style = style.combine(Effect::Underline);
+                }
+                if day == self.cursor && printer.focused {
+                    style = style.combine(Effect::Reverse);
+                }
+
+                printer.with_style(style, |printer| {
+                    printer.print((column * 3, row + 2), &format!("{:>2}", day.day()));
+                });
+            }
+        }
+    }
+
+    fn required_size(&mut self, _: Vec2) -> Vec2 {
+        Vec2::new(WIDTH, 8)
+    }
+
+    fn take_focus(&mut self, _: Direction) -> Result<EventResult, CannotFocus> {
+        Ok(EventResult::Consumed(None))
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Key(Key::Left) => self.move_to(self.cursor - Duration::days(1)),
+            Event::Key(Key::Right) => self.move_to(self.cursor + Duration::days(1)),
+            Event::Key(Key::Up) => self.move_to(self.cursor - Duration::days(7)),
+            Event::Key(Key::Down) => self.move_to(self.cursor + Duration::days(7)),
+            Event::Key(Key::PageUp) | Event::Char('<') => self.move_to(add_months(self.cursor, -1)),
+            Event::Key(Key::PageDown) | Event::Char('>') => self.move_to(add_months(self.cursor, 1)),
+            Event::Key(Key::Enter) => {
+                let day = self.cursor;
+                EventResult::Consumed(Some(Callback::from_fn(move |s| select(s, day))))
+            },
+            _ => EventResult::Ignored
+        }
+    }
+}
+
+pub fn view() -> impl View {
+    Panel::new(CalendarView::new(Local::now().naive_local().date()).with_name("calendar"))
+        .title("Calendar")
+}
+
+/// Reloads the due and tracked days of the month on display, when the
+/// month, the day or the data changed.
+pub fn refresh(s: &mut Cursive) -> Result<(), &'static str> {
+    let month = match s.call_on_name("calendar", |view: &mut CalendarView| view.month()) {
+        Some(month) => month,
+        None => return Ok(())
+    };
+    let state = s.user_data::<AppState>().expect("App state");
+    let key = format!("{} {} {:?}",
+        month,
+        Local::now().naive_local().date(),
+        taskwarrior::data_modified(&state.data_dirs));
+    if state.loaded.get("calendar") == Some(&key) {
+        return Ok(());
+    }
+    let next_month = add_months(month, 1);
+    let in_month = |day: &NaiveDate| *day >= month && *day < next_month;
+
+    let mut text = String::new();
+    let pending = taskwarrior::get_custom_report(&mut text, "status:pending due.any:", &["uuid", "due.iso"])?;
+    let due: BTreeSet<NaiveDate> = pending.rows
+        .iter()
+        .filter_map(|row| taskwarrior::parse_timew_date(pending.cell(row, "due.iso")))
+        .map(|date| date.naive_local().date())
+        .filter(in_month)
+        .collect();
+
+    let mut text = String::new();
+    let range = format!("{} - {}", month, next_month);
+    let intervals = taskwarrior::get_interval_list_for(&mut text, &range)?;
+    let tracked: BTreeSet<NaiveDate> = timesheet::intervals_from_list(&intervals)
+        .iter()
+        .filter_map(|interval| NaiveDate::parse_from_str(&interval.date, "%Y-%m-%d").ok())
+        .filter(in_month)
+        .collect();
+
+    s.call_on_name("calendar", |view: &mut CalendarView| view.set_marks(due, tracked));
+    s.user_data::<AppState>().expect("App state").loaded.insert("calendar", key);
+
+    Ok(())
+}
+
+/// Narrows both panes down to `day`, or back to every day when it was
+/// already chosen.
+fn select(s: &mut Cursive, day: NaiveDate) {
+    let state = s.user_data::<AppState>().expect("App state");
+    state.day = if state.day == Some(day) { None } else { Some(day) };
+}
+
+/// The weeks of the month, Monday first, with `None` outside the month.
+pub fn weeks(month: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let next_month = add_months(month, 1);
+    let mut day = month - Duration::days(month.weekday().num_days_from_monday() as i64);
+    let mut weeks = Vec::new();
+
+    while day < next_month {
+        let mut week = [None; 7];
+        for slot in week.iter_mut() {
+            if day >= month && day < next_month {
+                *slot = Some(day);
+            }
+            day += Duration::days(1);
+        }
+        weeks.push(week);
+    }
+
+    weeks
+}
+
+fn first_of_month(day: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd(day.year(), day.month(), 1)
+}
+
+/// The same day `months` later, kept within shorter months.
+pub fn add_months(day: NaiveDate, months: i32) -> NaiveDate {
+    let index = day.year() * 12 + day.month0() as i32 + months;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+
+    (1..=day.day())
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .unwrap_or_else(|| NaiveDate::from_ymd(year, month, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_grid() {
+        let weeks = weeks(NaiveDate::from_ymd(2026, 10, 1));
+
+        assert_eq!(5, weeks.len());
+        assert_eq!([None, None, None], weeks[0][..3]);
+        assert_eq!(Some(NaiveDate::from_ymd(2026, 10, 1)), weeks[0][3]);
+        assert_eq!(Some(NaiveDate::from_ymd(2026, 10, 31)), weeks[4][5]);
+        assert_eq!(None, weeks[4][6]);
+    }
+
+    #[test]
+    fn month_steps() {
+        assert_eq!(NaiveDate::from_ymd(2026, 2, 28), add_months(NaiveDate::from_ymd(2026, 1, 31), 1));
+        assert_eq!(NaiveDate::from_ymd(2025, 12, 15), add_months(NaiveDate::from_ymd(2026, 1, 15), -1));
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use cursive::event::Key;
+use cursive::theme::ColorStyle;
+use cursive::traits::*;
+use cursive::vec::Vec2;
+use cursive::view::View;
+use cursive::views::{Dialog, OnEventView};
+use cursive::{Cursive, Printer};
+
+use crate::taskwarrior;
+use crate::timesheet;
+
+const VERTICAL: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+const HORIZONTAL: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+const DAYS: i64 = 14;
+const WEEKS: i64 = 12;
+const LABEL_WIDTH: usize = 16;
+
+/// When a task was entered and, unless still pending, when it ended.
+type TaskDates = (DateTime<Local>, Option<DateTime<Local>>);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    Burndown,
+    Hours,
+    Tags
+}
+
+impl Kind {
+    fn next(self) -> Kind {
+        match self {
+            Kind::Burndown => Kind::Hours,
+            Kind::Hours => Kind::Tags,
+            Kind::Tags => Kind::Burndown
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Period {
+    Day,
+    Week
+}
+
+/// Labelled values, drawn as columns or, for `horizontal`, as rows.
+pub struct Chart {
+    pub title: String,
+    pub bars: Vec<(String, f64)>,
+    pub horizontal: bool
+}
+
+pub struct ChartView {
+    kind: Kind,
+    period: Period,
+    chart: Result<Chart, String>
+}
+
+impl ChartView {
+    pub fn new(kind: Kind, period: Period) -> Self {
+        let chart = load(kind, period).map_err(|e| e.to_string());
+        Self { kind, period, chart }
+    }
+
+    fn switch(&mut self, kind: Kind, period: Period) {
+        *self = ChartView::new(kind, period);
+    }
+
+    fn draw_columns(&self, printer: &Printer, chart: &Chart) {
+        let height = printer.size.y.saturating_sub(3);
+        let values: Vec<f64> = chart.bars.iter().map(|(_, value)| *value).collect();
+        let max = values.iter().cloned().fold(0.0, f64::max);
+
+        printer.print((0, 1), &format!("{:>5.1}", max));
+        printer.print((0, height), &format!("{:>5}", 0));
+        for (row, line) in vertical_bars(&values, height).iter().enumerate() {
+            for (column, block) in line.iter().enumerate() {
+                printer.with_color(ColorStyle::secondary(), |printer| {
+                    printer.print((7 + column * 4, row + 1), &block.repeat(3));
+                });
+            }
+        }
+        for (column, (label, _)) in chart.bars.iter().enumerate() {
+            printer.print((7 + column * 4, height + 1), label);
+        }
+    }
+
+    fn draw_rows(&self, printer: &Printer, chart: &Chart) {
+        let width = printer.size.x.saturating_sub(LABEL_WIDTH + 10);
+        let max = chart.bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+
+        for (row, (label, value)) in chart.bars.iter().enumerate().take(printer.size.y.saturating_sub(1)) {
+            let label: String = label.chars().take(LABEL_WIDTH - 1).collect();
+            printer.print((0, row + 1), &label);
+            printer.with_color(ColorStyle::secondary(), |printer| {
+                printer.print((LABEL_WIDTH, row + 1), &horizontal_bar(*value, max, width));
+            });
+            let bar_width = (*value / max * width as f64).ceil() as usize;
+            printer.print((LABEL_WIDTH + bar_width + 1, row + 1), &format!("{:.1}", value));
+        }
+    }
+}
+
+impl View for ChartView {
+    fn draw(&self, printer: &Printer) {
+        let chart = match &self.chart {
+            Ok(chart) => chart,
+            Err(e) => return printer.print((0, 0), e)
+        };
+
+        printer.with_color(ColorStyle::title_primary(), |printer| {
+            printer.print((0, 0), &chart.title);
+        });
+        if chart.bars.is_empty() {
+            printer.print((0, 2), "Nothing to show");
+        } else if chart.horizontal {
+            self.draw_rows(printer, chart);
+        } else {
+            self.draw_columns(printer, chart);
+        }
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        constraint
+    }
+}
+
+/// Opens the charts; Tab switches chart and `w` days and weeks.
+pub fn show(s: &mut Cursive) {
+    s.add_layer(OnEventView::new(
+        Dialog::around(ChartView::new(Kind::Burndown, Period::Day).with_name("chart").min_size((70, 20)))
+            .title("Charts — Tab: next chart, w: days/weeks")
+            .dismiss_button("Close"))
+        .on_event(Key::Tab, |s| {
+            s.call_on_name("chart", |view: &mut ChartView| view.switch(view.kind.next(), view.period));
+        })
+        .on_event('w', |s| {
+            s.call_on_name("chart", |view: &mut ChartView| {
+                let period = match view.period {
+                    Period::Day => Period::Week,
+                    Period::Week => Period::Day
+                };
+                view.switch(view.kind, period);
+            });
+        })
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+fn load<'a>(kind: Kind, period: Period) -> Result<Chart, &'a str> {
+    let buckets = buckets(Local::now().naive_local().date(), period);
+    let unit = match period {
+        Period::Day => "day",
+        Period::Week => "week"
+    };
+
+    match kind {
+        Kind::Burndown => {
+            let tasks = task_dates()?;
+            let bars = buckets
+                .iter()
+                .map(|(label, _, end)| (label.clone(), pending_at(&tasks, local_midnight(*end)) as f64))
+                .collect();
+
+            Ok(Chart { title: format!("Pending tasks per {}", unit), bars, horizontal: false })
+        },
+        Kind::Hours => {
+            let intervals = intervals(&buckets)?;
+            let bars = buckets
+                .iter()
+                .map(|(label, start, end)| {
+                    let seconds: u64 = intervals
+                        .iter()
+                        .filter(|(date, _, _)| date >= start && date < end)
+                        .map(|(_, _, seconds)| seconds)
+                        .sum();
+                    (label.clone(), seconds as f64 / 3600.0)
+                })
+                .collect();
+
+            Ok(Chart { title: format!("Hours tracked per {}", unit), bars, horizontal: false })
+        },
+        Kind::Tags => {
+            let mut totals: BTreeMap<String, u64> = BTreeMap::new();
+            for (_, tags, seconds) in intervals(&buckets)? {
+                for tag in tags.split(", ").filter(|tag| !tag.is_empty()) {
+                    *totals.entry(tag.to_string()).or_insert(0) += seconds;
+                }
+            }
+
+            let mut bars: Vec<(String, f64)> = totals
+                .into_iter()
+                .map(|(tag, seconds)| (tag, seconds as f64 / 3600.0))
+                .collect();
+            bars.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+            Ok(Chart { title: format!("Hours per tag, last {} {}s", buckets.len(), unit), bars, horizontal: true })
+        }
+    }
+}
+
+/// The last days or weeks up to `today`, as (label, start, end) with the
+/// end excluded.
+pub fn buckets(today: NaiveDate, period: Period) -> Vec<(String, NaiveDate, NaiveDate)> {
+    match period {
+        Period::Day => (0..DAYS)
+            .rev()
+            .map(|ago| today - Duration::days(ago))
+            .map(|day| (day.format("%d").to_string(), day, day + Duration::days(1)))
+            .collect(),
+        Period::Week => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            (0..WEEKS)
+                .rev()
+                .map(|ago| monday - Duration::weeks(ago))
+                .map(|start| (start.format("W%V").to_string(), start, start + Duration::weeks(1)))
+                .collect()
+        }
+    }
+}
+
+/// Tasks still pending at `at`: entered before and not yet ended.
+pub fn pending_at(tasks: &[TaskDates], at: DateTime<Local>) -> usize {
+    tasks
+        .iter()
+        .filter(|(entry, end)| *entry < at && end.is_none_or(|end| end >= at))
+        .count()
+}
+
+/// Entry and end dates of every task, from `task export`.
+fn task_dates<'a>() -> Result<Vec<TaskDates>, &'a str> {
+    Ok(taskwarrior::export_tasks("status.not:recurring")?
+        .lines()
+        .filter_map(|line| {
+            let entry = taskwarrior::parse_timew_date(json_field(line, "entry")?)?;
+            let end = json_field(line, "end").and_then(taskwarrior::parse_timew_date);
+            Some((entry, end))
+        })
+        .collect())
+}
+
+/// Date, tags and duration of the intervals in the buckets.
+fn intervals<'a>(buckets: &[(String, NaiveDate, NaiveDate)]) -> Result<Vec<(NaiveDate, String, u64)>, &'a str> {
+    let (start, end) = match (buckets.first(), buckets.last()) {
+        (Some(first), Some(last)) => (first.1, last.2),
+        _ => return Ok(Vec::new())
+    };
+
+    let mut text = String::new();
+    let list = taskwarrior::get_interval_list_for(&mut text, &format!("{} - {}", start, end))?;
+
+    Ok(timesheet::intervals_from_list(&list)
+        .into_iter()
+        .filter_map(|interval| {
+            let date = NaiveDate::parse_from_str(&interval.date, "%Y-%m-%d").ok()?;
+            Some((date, interval.tags, interval.seconds))
+        })
+        .collect())
+}
+
+fn local_midnight(day: NaiveDate) -> DateTime<Local> {
+    Local.from_local_datetime(&day.and_hms(0, 0, 0))
+        .earliest()
+        .unwrap_or_else(Local::now)
+}
+
+/// The string value of `key` in a line of `task export`, which is enough
+/// for dates and UUIDs since they never need escaping.
+pub fn json_field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("\"{}\":\"", key))? + key.len() + 4;
+    let length = line[start..].find('"')?;
+
+    Some(&line[start..start + length])
+}
+
+/// Rows of block characters, top first, one character per value, scaled
+/// so the largest value fills `height` rows.
+pub fn vertical_bars(values: &[f64], height: usize) -> Vec<Vec<&'static str>> {
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    let eighths: Vec<usize> = values
+        .iter()
+        .map(|value| if max > 0.0 { (value / max * (height * 8) as f64).round() as usize } else { 0 })
+        .collect();
+
+    (0..height)
+        .rev()
+        .map(|level| eighths
+            .iter()
+            .map(|eighths| VERTICAL[eighths.saturating_sub(level * 8).min(8)])
+            .collect())
+        .collect()
+}
+
+/// A bar of `value` where `max` takes the full `width`.
+pub fn horizontal_bar(value: f64, max: f64, width: usize) -> String {
+    if max <= 0.0 {
+        return String::new();
+    }
+
+    let eighths = (value / max * (width * 8) as f64).round() as usize;
+    format!("{}{}", "█".repeat(eighths / 8), HORIZONTAL[eighths % 8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns() {
+        assert_eq!(vec![vec![" ", "▄", "█"], vec!["▄", "█", "█"]], vertical_bars(&[1.0, 3.0, 4.0], 2));
+        assert_eq!(vec![vec![" ", " "]], vertical_bars(&[0.0, 0.0], 1));
+    }
+
+    #[test]
+    fn rows() {
+        assert_eq!("█████", horizontal_bar(10.0, 10.0, 5));
+        assert_eq!("██▌", horizontal_bar(5.0, 10.0, 5));
+        assert_eq!("", horizontal_bar(1.0, 0.0, 5));
+    }
+
+    #[test]
+    fn burndown() {
+        let at = |day| local_midnight(NaiveDate::from_ymd(2026, 10, day));
+        let tasks = vec![(at(1), None), (at(2), Some(at(4))), (at(5), None)];
+
+        assert_eq!(0, pending_at(&tasks, at(1)));
+        assert_eq!(2, pending_at(&tasks, at(3)));
+        assert_eq!(1, pending_at(&tasks, at(5)));
+    }
+
+    #[test]
+    fn export_fields() {
+        let line = r#"{"id":1,"description":"Buy \"milk\"","end":"20261020T080000Z","entry":"20261019T101010Z"}"#;
+        assert_eq!(Some("20261019T101010Z"), json_field(line, "entry"));
+        assert_eq!(Some("20261020T080000Z"), json_field(line, "end"));
+        assert_eq!(None, json_field(line, "due"));
+    }
+
+    #[test]
+    fn week_buckets() {
+        let weeks = buckets(NaiveDate::from_ymd(2026, 10, 21), Period::Week);
+
+        assert_eq!(WEEKS as usize, weeks.len());
+        assert_eq!(("W43".to_string(), NaiveDate::from_ymd(2026, 10, 19), NaiveDate::from_ymd(2026, 10, 26)), weeks[11]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::Config;
+use crate::diagnostics::Diagnostics;
+use crate::status::{self, StatusFormat};
+use crate::taskwarrior::{self, ActivePolicy};
+use crate::timesheet::{self, Format, Rounding, RoundingMode};
+
+const USAGE: &str = "Usage: lazytask [COMMAND] [--json]
+
+Without a command the interactive UI is started.
+
+Commands:
+  status [--tasks] [--format plain|json|tmux] [--watch]
+                              Show the running task and today's total, or
+                              list the active tasks with --tasks
+  toggle <id> [--stop-others|--keep-others]
+                              Start or stop a task; the confirm policy
+                              stops the other active tasks unless told not to
+  doctor                      Check the task and timew setup
+  report [--day|--week|--month|--range RANGE]
+                              Show the tracked time per tag
+  export [--format csv|json|markdown] [--range RANGE] [--round MINUTES]
+         [--rounding nearest|up|down] [--totals] [--output FILE]
+                              Export intervals as a timesheet";
+
+/// Runs a non-interactive subcommand and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => return fail(e)
+    };
+
+    let json = args.iter().any(|a| a == "--json");
+    let args: Vec<&str> = args
+        .iter()
+        .map(|a| a.as_str())
+        .filter(|a| *a != "--json")
+        .collect();
+
+    let result = match args.first() {
+        Some(&"status") => status(&config, &args[1..], json),
+        Some(&"toggle") => check_tracking(&config).and_then(|_| toggle(&config, &args[1..], json)),
+        Some(&"report") => report(&args[1..], json),
+        Some(&"export") => export(&config, &args[1..], json),
+        Some(&"doctor") => doctor(&config, json),
+        Some(&"-h") | Some(&"--help") | Some(&"help") => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        _ => Err(USAGE)
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => fail(e)
+    }
+}
+
+fn doctor<'a>(config: &Config, json: bool) -> Result<(), &'a str> {
+    let diagnostics = Diagnostics::run();
+    if json {
+        println!("{}", diagnostics.report_json(config.timew_mode));
+    } else {
+        println!("{}", diagnostics.report(config.timew_mode));
+    }
+
+    match diagnostics.fatal() {
+        Some(_) => Err("task or timew is missing"),
+        None => Ok(())
+    }
+}
+
+/// Checks the setup before starting or stopping a task, and whether
+/// lazytask has to track the time itself.
+fn check_tracking<'a>(config: &Config) -> Result<(), &'a str> {
+    let diagnostics = Diagnostics::run();
+    if let Some(problem) = diagnostics.fatal() {
+        eprintln!("lazytask: {}", problem);
+        return Err("run `lazytask doctor` for details");
+    }
+
+    taskwarrior::set_timew_fallback(config.timew_mode.uses_fallback(diagnostics.hook_installed));
+    taskwarrior::set_timew_hook(diagnostics.hook_installed);
+    Ok(())
+}
+
+fn status<'a>(config: &Config, args: &[&str], json: bool) -> Result<(), &'a str> {
+    let mut format = if json { StatusFormat::Json } else { config.status_format };
+    let mut watch = false;
+    let mut tasks = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--format" => {
+                format = StatusFormat::parse(value(args.next())?).ok_or("Unknown format")?;
+            },
+            "--watch" => watch = true,
+            "--tasks" => tasks = true,
+            _ => return Err(USAGE)
+        }
+    }
+
+    if tasks {
+        return active_tasks(json);
+    }
+
+    let mut last = String::new();
+    loop {
+        let line = status::render(&status::current()?, format,
+            &config.status_template, &config.status_idle_template);
+
+        if line != last {
+            println!("{}", line);
+            io::stdout().flush().map_err(|_| "Could not write status")?;
+            last = line;
+        }
+
+        if !watch {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn active_tasks<'a>(json: bool) -> Result<(), &'a str> {
+    let mut text = String::new();
+    let active = taskwarrior::get_active_task_list(&mut text)?;
+
+    if json {
+        let tasks: Vec<String> = active.rows
+            .iter()
+            .map(|row| format!("{{\"id\":{},\"description\":{}}}",
+                timesheet::json_string(active.cell(row, "ID")),
+                timesheet::json_string(active.cell(row, "Description"))))
+            .collect();
+        println!("[{}]", tasks.join(","));
+    } else {
+        for row in &active.rows {
+            println!("{} {}", active.cell(row, "ID"), active.cell(row, "Description"));
+        }
+    }
+
+    Ok(())
+}
+
+fn toggle<'a>(config: &Config, args: &[&str], json: bool) -> Result<(), &'a str> {
+    let mut stop_others = config.active_policy != ActivePolicy::Multiple;
+    let mut task_id = None;
+    for arg in args {
+        match *arg {
+            "--stop-others" => stop_others = true,
+            "--keep-others" => stop_others = false,
+            id if task_id.is_none() && !id.starts_with('-') => task_id = Some(id),
+            _ => return Err(USAGE)
+        }
+    }
+
+    let toggle = taskwarrior::toggle_task(task_id.ok_or(USAGE)?, stop_others)?;
+    if json {
+        let stopped: Vec<String> = toggle.stopped.iter().map(|uuid| timesheet::json_string(uuid)).collect();
+        println!("{{\"started\":{},\"stopped\":[{}]}}",
+            toggle.started.as_deref().map(timesheet::json_string).unwrap_or_else(|| "null".to_string()),
+            stopped.join(","));
+        return Ok(());
+    }
+
+    for uuid in &toggle.stopped {
+        println!("Stopped {}", taskwarrior::get_task_attribute(uuid, "description")?);
+    }
+    if let Some(uuid) = &toggle.started {
+        println!("Started {}", taskwarrior::get_task_attribute(uuid, "description")?);
+    }
+
+    Ok(())
+}
+
+fn report<'a>(args: &[&str], json: bool) -> Result<(), &'a str> {
+    let mut range = ":day".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--day" => range = ":day".to_string(),
+            "--week" => range = ":week".to_string(),
+            "--month" => range = ":month".to_string(),
+            "--range" => range = value(args.next())?.to_string(),
+            _ => return Err(USAGE)
+        }
+    }
+
+    let mut text = String::new();
+    let list = taskwarrior::get_interval_list_for(&mut text, &range)?;
+    let totals = timesheet::aggregate(&timesheet::intervals_from_list(&list));
+
+    if json {
+        print!("{}", timesheet::render_totals(Format::Json, &totals, &Rounding::none()));
+        return Ok(());
+    }
+
+    // Tags may hold wide characters, so pad by display width
+    let width = totals.iter().map(|(tags, _)| tags.width()).max().unwrap_or_default().max(5);
+    let pad = |text: &str| format!("{}{}", text, " ".repeat(width - text.width()));
+    for (tags, seconds) in &totals {
+        println!("{} {:>9}", pad(tags), timesheet::format_duration(*seconds));
+    }
+    let total: u64 = totals.iter().map(|(_, seconds)| seconds).sum();
+    println!("{} {:>9}", pad("Total"), timesheet::format_duration(total));
+
+    Ok(())
+}
+
+fn export<'a>(config: &Config, args: &[&str], json: bool) -> Result<(), &'a str> {
+    let mut format = if json { Format::Json } else { config.export_format };
+    let mut rounding = config.export_rounding;
+    let mut range = String::new();
+    let mut output: Option<String> = None;
+    let mut totals = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--format" => {
+                format = Format::parse(value(args.next())?).ok_or("Unknown format")?;
+            },
+            "--range" => range = value(args.next())?.to_string(),
+            "--round" => {
+                rounding.minutes = value(args.next())?.parse().map_err(|_| "Invalid --round")?;
+            },
+            "--rounding" => {
+                rounding.mode = RoundingMode::parse(value(args.next())?).ok_or("Unknown rounding")?;
+            },
+            "--totals" => totals = true,
+            "--output" => output = Some(value(args.next())?.to_string()),
+            _ => return Err(USAGE)
+        }
+    }
+
+    let text = timesheet::export(&range, format, &rounding, totals)?;
+    match output {
+        Some(path) => fs::write(path, text).map_err(|_| "Could not write export file"),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn value<'a, 'b>(arg: Option<&&'a str>) -> Result<&'a str, &'b str> {
+    arg.copied().ok_or("Missing option value")
+}
+
+fn fail(message: &str) -> i32 {
+    eprintln!("lazytask: {}", message);
+    1
+}
@@ -0,0 +1,57 @@
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{Checkbox, Dialog, ListView, OnEventView};
+
+use crate::config::Config;
+use crate::cursive_simple_table_view::SimpleTableView;
+use crate::state::AppState;
+
+/// Lets the user pick the columns shown for the current report and
+/// remembers the choice for the next start.
+pub fn show_chooser(s: &mut Cursive) {
+    let titles = s.call_on_name("tasks_table", |view: &mut SimpleTableView| view.column_titles())
+        .unwrap_or_default();
+    let report = s.user_data::<AppState>().expect("App state").report.clone();
+
+    let mut list = ListView::new();
+    for (index, (title, shown)) in titles.iter().enumerate() {
+        list.add_child(title, Checkbox::new()
+            .with_checked(*shown)
+            .with_name(format!("column_{}", index)));
+    }
+
+    s.add_layer(OnEventView::new(
+        Dialog::around(list.scrollable())
+            .title(format!("Columns of {}", report))
+            .button("Save", move |s| save(s, &titles))
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+fn save(s: &mut Cursive, titles: &[(String, bool)]) {
+    let visible: Vec<String> = titles
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            s.call_on_name(&format!("column_{}", index), |view: &mut Checkbox| view.is_checked())
+                .unwrap_or_default()
+        })
+        .map(|(_, (title, _))| title.clone())
+        .collect();
+    if visible.is_empty() {
+        s.add_layer(Dialog::info("Choose at least one column"));
+        return;
+    }
+    s.pop_layer();
+
+    s.call_on_name("tasks_table", |view: &mut SimpleTableView| view.set_visible_columns(&visible));
+    let state = s.user_data::<AppState>().expect("App state");
+    let report = state.report.clone();
+    state.config.columns.insert(report.clone(), visible.clone());
+
+    if let Err(e) = Config::save_columns(&report, &visible) {
+        s.add_layer(Dialog::info(e));
+    }
+}
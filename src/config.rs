@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use toml::value::Table;
+use toml::Value;
+
+use crate::diagnostics::TimewMode;
+use crate::pomodoro::{Durations, LogMode};
+use crate::status::{self, StatusFormat};
+use crate::taskwarrior::ActivePolicy;
+use crate::timesheet::{Format, Rounding, RoundingMode};
+
+pub struct Config {
+    pub export_format: Format,
+    pub export_rounding: Rounding,
+    pub status_format: StatusFormat,
+    pub status_template: String,
+    pub status_idle_template: String,
+    pub pomodoro_durations: Durations,
+    pub pomodoro_log: LogMode,
+    pub idle_threshold_hours: u64,
+    pub idle_midnight: bool,
+    pub active_policy: ActivePolicy,
+    pub timew_mode: TimewMode,
+    pub projects_range: String,
+    /// Visible columns per report; reports not listed show them all.
+    pub columns: BTreeMap<String, Vec<String>>
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            export_format: Format::Csv,
+            export_rounding: Rounding::none(),
+            status_format: StatusFormat::Plain,
+            status_template: status::DEFAULT_TEMPLATE.to_string(),
+            status_idle_template: status::DEFAULT_IDLE_TEMPLATE.to_string(),
+            pomodoro_durations: Durations::default(),
+            pomodoro_log: LogMode::Annotation,
+            idle_threshold_hours: 10,
+            idle_midnight: true,
+            active_policy: ActivePolicy::Single,
+            timew_mode: TimewMode::Auto,
+            projects_range: ":week".to_string(),
+            columns: BTreeMap::new()
+        }
+    }
+}
+
+impl Config {
+    /// Reads `$XDG_CONFIG_HOME/lazytask/config.toml` (or `~/.config/...`).
+    /// A missing file just means the defaults are used.
+    pub fn load<'a>() -> Result<Config, &'a str> {
+        let mut config = match config_path() {
+            Some(path) if path.exists() => {
+                let text = fs::read_to_string(path)
+                    .map_err(|_| "Could not read config file")?;
+                Config::parse(&text)?
+            },
+            _ => Config::default()
+        };
+
+        // Choices made in the column chooser win over the config file
+        if let Some(path) = columns_path().filter(|path| path.exists()) {
+            let text = fs::read_to_string(path)
+                .map_err(|_| "Could not read saved columns")?;
+            let value = parse_document(&text)?;
+            config.columns.extend(parse_columns(value.as_table())?);
+        }
+
+        Ok(config)
+    }
+
+    pub fn parse<'a>(text: &str) -> Result<Config, &'a str> {
+        let value = parse_document(text)?;
+        let mut config = Config::default();
+
+        if let Some(export) = value.get("export") {
+            if let Some(format) = get_str(export, "format") {
+                config.export_format = Format::parse(format)
+                    .ok_or("Unknown export.format")?;
+            }
+            if let Some(minutes) = get_int(export, "round") {
+                config.export_rounding.minutes = minutes as u64;
+            }
+            if let Some(mode) = get_str(export, "rounding") {
+                config.export_rounding.mode = RoundingMode::parse(mode)
+                    .ok_or("Unknown export.rounding")?;
+            }
+        }
+
+        if let Some(status) = value.get("status") {
+            if let Some(format) = get_str(status, "format") {
+                config.status_format = StatusFormat::parse(format)
+                    .ok_or("Unknown status.format")?;
+            }
+            if let Some(template) = get_str(status, "template") {
+                config.status_template = template.to_string();
+            }
+            if let Some(template) = get_str(status, "idle_template") {
+                config.status_idle_template = template.to_string();
+            }
+        }
+
+        if let Some(pomodoro) = value.get("pomodoro") {
+            let durations = &mut config.pomodoro_durations;
+            if let Some(minutes) = get_int(pomodoro, "work") {
+                durations.work = minutes as u64;
+            }
+            if let Some(minutes) = get_int(pomodoro, "short_break") {
+                durations.short_break = minutes as u64;
+            }
+            if let Some(minutes) = get_int(pomodoro, "long_break") {
+                durations.long_break = minutes as u64;
+            }
+            if let Some(count) = get_int(pomodoro, "long_break_every") {
+                durations.long_break_every = count as u32;
+            }
+            if let Some(log) = get_str(pomodoro, "log") {
+                config.pomodoro_log = LogMode::parse(log)
+                    .ok_or("Unknown pomodoro.log")?;
+            }
+        }
+
+        if let Some(idle) = value.get("idle") {
+            if let Some(hours) = get_int(idle, "threshold_hours") {
+                config.idle_threshold_hours = hours as u64;
+            }
+            if let Some(midnight) = idle.get("midnight").and_then(Value::as_bool) {
+                config.idle_midnight = midnight;
+            }
+        }
+
+        if let Some(tracking) = value.get("tracking") {
+            if let Some(policy) = get_str(tracking, "active_policy") {
+                config.active_policy = ActivePolicy::parse(policy)
+                    .ok_or("Unknown tracking.active_policy")?;
+            }
+            if let Some(mode) = get_str(tracking, "timewarrior") {
+                config.timew_mode = TimewMode::parse(mode)
+                    .ok_or("Unknown tracking.timewarrior")?;
+            }
+        }
+
+        if let Some(projects) = value.get("projects") {
+            if let Some(range) = get_str(projects, "range") {
+                config.projects_range = range.to_string();
+            }
+        }
+
+        config.columns = parse_columns(value.get("columns").and_then(Value::as_table))?;
+
+        Ok(config)
+    }
+
+    /// Remembers the visible columns of `report` in
+    /// `$XDG_STATE_HOME/lazytask/columns.toml`, leaving the config file as
+    /// the user wrote it.
+    pub fn save_columns<'a>(report: &str, visible: &[String]) -> Result<(), &'a str> {
+        let path = columns_path().ok_or("Could not find the state directory")?;
+        let text = fs::read_to_string(&path).unwrap_or_default();
+        let text = set_columns(&text, report, visible)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| "Could not create the state directory")?;
+        }
+        fs::write(&path, text).map_err(|_| "Could not write saved columns")
+    }
+}
+
+/// Visible columns per report, from a table of lists of column names.
+fn parse_columns<'a>(columns: Option<&Table>) -> Result<BTreeMap<String, Vec<String>>, &'a str> {
+    let mut parsed = BTreeMap::new();
+    for (report, visible) in columns.into_iter().flatten() {
+        let visible = visible.as_array()
+            .ok_or("columns entries must be lists of column names")?
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|column| column.to_string())
+            .collect();
+        parsed.insert(report.clone(), visible);
+    }
+
+    Ok(parsed)
+}
+
+/// The saved columns text with `visible` set for `report`.
+fn set_columns<'a>(text: &str, report: &str, visible: &[String]) -> Result<String, &'a str> {
+    let mut value = parse_document(text)?;
+    let table = value.as_table_mut().ok_or("Could not parse saved columns")?;
+    table.insert(report.to_string(),
+        Value::Array(visible.iter().map(|column| Value::String(column.clone())).collect()));
+
+    toml::to_string(&value).map_err(|_| "Could not write saved columns")
+}
+
+/// Parses a whole file; `str::parse::<Value>` reads a single value in
+/// newer toml versions.
+fn parse_document<'a>(text: &str) -> Result<Value, &'a str> {
+    toml::from_str::<Table>(text)
+        .map(Value::Table)
+        .map_err(|_| "Could not parse config file")
+}
+
+fn config_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config")
+    };
+
+    Some(base.join("lazytask").join("config.toml"))
+}
+
+fn columns_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_STATE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local").join("state")
+    };
+
+    Some(base.join("lazytask").join("columns.toml"))
+}
+
+fn get_str<'a>(table: &'a Value, key: &str) -> Option<&'a str> {
+    table.get(key).and_then(Value::as_str)
+}
+
+fn get_int(table: &Value, key: &str) -> Option<i64> {
+    table.get(key).and_then(Value::as_integer).filter(|v| *v >= 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_export() {
+        let config = Config::parse("
+[export]
+format = \"markdown\"
+round = 15
+rounding = \"up\"").unwrap();
+
+        assert_eq!(Format::Markdown, config.export_format);
+        assert_eq!(Rounding { minutes: 15, mode: RoundingMode::Up }, config.export_rounding);
+    }
+
+    #[test]
+    fn parse_empty() {
+        let config = Config::parse("").unwrap();
+
+        assert_eq!(Format::Csv, config.export_format);
+        assert_eq!(0, config.export_rounding.minutes);
+        assert_eq!(StatusFormat::Plain, config.status_format);
+        assert_eq!(ActivePolicy::Single, config.active_policy);
+    }
+
+    #[test]
+    fn parse_status() {
+        let config = Config::parse("
+[status]
+format = \"waybar\"
+template = \"{id}: {elapsed}\"").unwrap();
+
+        assert_eq!(StatusFormat::Json, config.status_format);
+        assert_eq!("{id}: {elapsed}", config.status_template);
+        assert_eq!(status::DEFAULT_IDLE_TEMPLATE, config.status_idle_template);
+    }
+
+    #[test]
+    fn parse_pomodoro() {
+        let config = Config::parse("
+[pomodoro]
+work = 50
+short_break = 10
+log = \"tag\"").unwrap();
+
+        assert_eq!(50, config.pomodoro_durations.work);
+        assert_eq!(10, config.pomodoro_durations.short_break);
+        assert_eq!(15, config.pomodoro_durations.long_break);
+        assert_eq!(LogMode::Tag, config.pomodoro_log);
+    }
+
+    #[test]
+    fn save_columns() {
+        let visible = vec!["ID".to_string(), "Description".to_string()];
+        let text = set_columns("waiting = [\"ID\"]\n", "next", &visible).unwrap();
+        let value = parse_document(&text).unwrap();
+        let columns = parse_columns(value.as_table()).unwrap();
+
+        assert_eq!(Some(&visible), columns.get("next"));
+        assert_eq!(Some(&vec!["ID".to_string()]), columns.get("waiting"));
+    }
+
+    #[test]
+    fn parse_columns_section() {
+        let config = Config::parse("
+[columns]
+next = [\"ID\", \"Urgency\"]").unwrap();
+
+        assert_eq!(Some(&vec!["ID".to_string(), "Urgency".to_string()]), config.columns.get("next"));
+    }
+}
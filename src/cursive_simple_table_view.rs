@@ -1,25 +1,48 @@
-use std::cmp;
+use std::cmp::{self, Ordering};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use cursive::vec::Vec2;
 use cursive::align::HAlign;
 use cursive::direction::Direction;
-use cursive::event::{Event, EventResult, Key};
+use cursive::event::{Event, EventResult, Key, MouseButton, MouseEvent};
 use cursive::theme;
 use cursive::view::{ScrollBase, CannotFocus, View};
 use cursive::With;
-use cursive::Printer;
+use cursive::{Cursive, Printer};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 pub struct SimpleTableView {
     enabled: bool,
     scrollbase: ScrollBase,
     last_size: Vec2,
+    needs_layout: bool,
 
     columns: Vec<TableColumn>,
     rows: Vec<Vec<String>>,
+    row_keys: Vec<String>,
+    /// What a row is compared by to tell whether it changed.
+    row_versions: BTreeMap<String, String>,
+    change_highlight: Option<Duration>,
+    changed_keys: BTreeMap<String, Instant>,
+    /// The rows in display order; `focus` is a position in it.
+    order: Vec<usize>,
+    sort: Option<(usize, bool)>,
     focus: usize,
-    selected_rows: Vec<usize>
+    scroll_x: usize,
+    selected_rows: Vec<usize>,
+    marked_rows: Vec<usize>,
+    last_click: Option<(usize, Instant)>,
+    search: String,
+    on_submit: Option<SubmitCallback>
 }
 
+type SubmitCallback = Rc<dyn Fn(&mut Cursive, usize)>;
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
 impl Default for SimpleTableView {
     fn default() -> Self {
         Self::new()
@@ -32,17 +55,33 @@ impl SimpleTableView {
             enabled: true,
             scrollbase: ScrollBase::new(),
             last_size: Vec2::new(0, 0),
+            needs_layout: false,
 
             columns: Vec::new(),
             rows: Vec::new(),
+            row_keys: Vec::new(),
+            row_versions: BTreeMap::new(),
+            change_highlight: None,
+            changed_keys: BTreeMap::new(),
+            order: Vec::new(),
+            sort: None,
             focus: 0,
-            selected_rows: Vec::new()
+            scroll_x: 0,
+            selected_rows: Vec::new(),
+            marked_rows: Vec::new(),
+            last_click: None,
+            search: String::new(),
+            on_submit: None
         }
     }
 
     pub fn clear(&mut self) {
         self.rows.clear();
+        self.row_keys.clear();
+        self.row_versions.clear();
+        self.order.clear();
         self.selected_rows = Vec::new();
+        self.marked_rows = Vec::new();
         self.focus = 0;
     }
 
@@ -58,10 +97,15 @@ impl SimpleTableView {
         self.with(|t| t.set_selected_rows(indices))
     }
 
+    /// Rows drawn underlined, e.g. tasks marked for a bulk change.
+    pub fn set_marked_rows(&mut self, indices: Vec<usize>) {
+        self.marked_rows = indices;
+    }
+
     pub fn set_focus_row(&mut self, row_index: usize) {
         if !self.rows.is_empty() {
-            self.focus = cmp::min(self.rows.len() -1, row_index);
-            self.scrollbase.scroll_to(row_index);
+            self.focus = self.position_of(cmp::min(self.rows.len() -1, row_index));
+            self.scrollbase.scroll_to(self.focus);
         }
     }
 
@@ -69,8 +113,95 @@ impl SimpleTableView {
         if self.rows.is_empty() {
             None
         } else {
-            Some(self.focus)
+            Some(self.row_at(self.focus))
+        }
+    }
+
+    /// Called with the row on a double click.
+    pub fn set_on_submit<F: Fn(&mut Cursive, usize) + 'static>(&mut self, callback: F) {
+        self.on_submit = Some(Rc::new(callback));
+    }
+
+    pub fn on_submit<F: Fn(&mut Cursive, usize) + 'static>(self: SimpleTableView, callback: F) -> Self {
+        self.with(|t| t.set_on_submit(callback))
+    }
+
+    /// Sorts the rows by a column, or the other way round when they
+    /// already are. The order holds for later rows too.
+    pub fn sort_by(&mut self, column: usize) {
+        let focus_row = self.focus_row();
+        let ascending = self.sort != Some((column, true));
+        self.sort = Some((column, ascending));
+        self.sort_rows();
+        if let Some(row) = focus_row {
+            self.set_focus_row(row);
+        }
+    }
+
+    /// Highlights `pattern` in the cells, ignoring case, and moves the
+    /// focus to the first row from it on that contains it.
+    pub fn set_search(&mut self, pattern: &str) -> bool {
+        self.search = pattern.to_lowercase();
+        self.find_match(self.focus, true)
+    }
+
+    /// Moves the focus to the next row matching the search, or the
+    /// previous one, wrapping around.
+    pub fn search_next(&mut self, forward: bool) -> bool {
+        let count = self.rows.len();
+        if count == 0 {
+            return false;
+        }
+
+        let from = if forward { (self.focus + 1) % count } else { (self.focus + count - 1) % count };
+        self.find_match(from, forward)
+    }
+
+    fn find_match(&mut self, from: usize, forward: bool) -> bool {
+        let count = self.rows.len();
+        if self.search.is_empty() || count == 0 {
+            return false;
+        }
+
+        let found = (0..count)
+            .map(|step| if forward { (from + step) % count } else { (from + count - step) % count })
+            .find(|position| self.matches(self.row_at(*position)));
+        if let Some(position) = found {
+            self.focus = position;
+            self.scrollbase.scroll_to(position);
         }
+
+        found.is_some()
+    }
+
+    fn matches(&self, row_index: usize) -> bool {
+        self.rows[row_index]
+            .iter()
+            .any(|cell| cell.to_lowercase().contains(&self.search))
+    }
+
+    fn sort_rows(&mut self) {
+        let rows = &self.rows;
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        if let Some((column, ascending)) = self.sort {
+            order.sort_by(|a, b| {
+                let ordering = compare_cells(
+                    rows[*a].get(column).map_or("", |cell| cell.as_str()),
+                    rows[*b].get(column).map_or("", |cell| cell.as_str()));
+                if ascending { ordering } else { ordering.reverse() }
+            });
+        }
+        self.order = order;
+    }
+
+    /// The row shown at a position.
+    fn row_at(&self, position: usize) -> usize {
+        self.order.get(position).copied().unwrap_or(position)
+    }
+
+    /// The position a row is shown at.
+    fn position_of(&self, row_index: usize) -> usize {
+        self.order.iter().position(|row| *row == row_index).unwrap_or(row_index)
     }
 
     pub fn borrow_row(&mut self, index: usize) -> Option<&mut Vec<String>>{
@@ -78,8 +209,18 @@ impl SimpleTableView {
     }
 
     pub fn set_columns(&mut self, columns: Vec<TableColumn>) {
+        // Keep the rows and the layout while the columns stay the same
+        let same_titles = columns.len() == self.columns.len()
+            && columns.iter().zip(&self.columns).all(|(new, old)| new.title == old.title);
+        if same_titles {
+            return;
+        }
+
+        // The sorted column and the scroll offset belonged to the old columns
         self.columns = columns;
         self.clear();
+        self.sort = None;
+        self.scroll_x = 0;
 
         self.last_size = Vec2::new(0, 0);
     }
@@ -88,6 +229,53 @@ impl SimpleTableView {
         self.with(|t| t.set_columns(columns))
     }
 
+    /// Hides the columns whose title is not in `titles`.
+    pub fn set_visible_columns(&mut self, titles: &[String]) {
+        for column in self.columns.iter_mut() {
+            let hidden = !titles.contains(&column.title);
+            if column.hidden != hidden {
+                column.hidden = hidden;
+                self.needs_layout = true;
+            }
+        }
+    }
+
+    /// Highlights rows that are new or differ from the last `set_keyed_rows`
+    /// for `duration`.
+    pub fn set_change_highlight(&mut self, duration: Duration) {
+        self.change_highlight = Some(duration);
+    }
+
+    pub fn change_highlight(self: SimpleTableView, duration: Duration) -> Self {
+        self.with(|t| t.set_change_highlight(duration))
+    }
+
+    /// Column titles and whether each one is shown.
+    pub fn column_titles(&self) -> Vec<(String, bool)> {
+        self.columns
+            .iter()
+            .map(|column| (column.title.clone(), !column.hidden))
+            .collect()
+    }
+
+    /// The shown columns with their index in the rows.
+    fn visible_columns(&self) -> impl Iterator<Item = (usize, &TableColumn)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| !column.hidden)
+    }
+
+    /// Whether columns are cut off on the right.
+    fn overflows(&self) -> bool {
+        let width: usize = self.visible_columns()
+            .skip(self.scroll_x)
+            .map(|(_, column)| column.width + 1)
+            .sum();
+
+        width > self.last_size.x + 1
+    }
+
     pub fn set_rows<S: Into<String>>(&mut self, rows: Vec<Vec<S>>) {
         let rows: Vec<Vec<String>> = rows
             .into_iter()
@@ -106,18 +294,92 @@ impl SimpleTableView {
             }
         }
 
+        if rows != self.rows {
+            // Columns sized to their content may need to grow or shrink
+            self.needs_layout = true;
+        }
         self.rows = rows;
+        self.sort_rows();
+        self.row_keys = Vec::new();
+        self.row_versions = BTreeMap::new();
         self.selected_rows = Vec::new();
+        self.marked_rows = Vec::new();
         self.scrollbase
             .set_heights(self.last_size.y.saturating_sub(2), self.rows.len());
-        
-        self.set_focus_row(self.focus);
+
+        if !self.rows.is_empty() {
+            self.scrollbase.scroll_to(self.focus);
+        }
+    }
+
+    /// Replaces the rows like `set_rows`, but keeps the focus and its place
+    /// on screen on the row with the same key, e.g. the UUID of a task.
+    /// Rows with an empty key are only matched by position. A row counts as
+    /// changed when its version does, e.g. the modification time of a task,
+    /// so cells that change on their own, like an age, do not stand out.
+    pub fn set_keyed_rows<S: Into<String>>(&mut self, rows: Vec<Vec<S>>, keys: Vec<String>, versions: Vec<String>) {
+        let focus_key = self.row_keys.get(self.row_at(self.focus)).filter(|key| !key.is_empty()).cloned();
+        let screen_offset = self.focus.saturating_sub(self.scrollbase.start_line);
+        let versions: BTreeMap<String, String> = keys
+            .iter()
+            .cloned()
+            .zip(versions)
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+        let previous = std::mem::take(&mut self.row_versions);
+
+        self.set_rows(rows);
+        self.row_versions = versions;
+
+        if let Some(duration) = self.change_highlight {
+            // Nothing stands out on the first load
+            if !previous.is_empty() {
+                let now = Instant::now();
+                for (key, version) in self.row_versions.iter() {
+                    if previous.get(key) != Some(version) {
+                        self.changed_keys.insert(key.clone(), now);
+                    }
+                }
+            }
+            self.changed_keys.retain(|_, changed_at| changed_at.elapsed() < duration);
+        }
+
+        if let Some(index) = focus_key.and_then(|focus_key| keys.iter().position(|key| *key == focus_key)) {
+            self.focus = self.position_of(index);
+        }
+        self.row_keys = keys;
+
+        self.scrollbase.start_line = self.focus.saturating_sub(screen_offset);
+        self.scrollbase
+            .set_heights(self.last_size.y.saturating_sub(2), self.rows.len());
+        if !self.rows.is_empty() {
+            self.scrollbase.scroll_to(self.focus);
+        }
     }
 
     pub fn rows<S: Into<String>>(self: SimpleTableView, rows: Vec<Vec<S>>) -> Self {
         self.with(|t| t.set_rows(rows))
     }
 
+    /// Whether the row changed within the highlight duration.
+    fn is_changed(&self, index: usize) -> bool {
+        match (self.change_highlight, self.row_keys.get(index)) {
+            (Some(duration), Some(key)) => self.changed_keys
+                .get(key)
+                .is_some_and(|changed_at| changed_at.elapsed() < duration),
+            _ => false
+        }
+    }
+
+    /// The widest value of the column, its title included.
+    fn content_width(&self, index: usize) -> usize {
+        self.rows
+            .iter()
+            .filter_map(|row| row.get(index))
+            .map(|value| value.width())
+            .fold(self.columns[index].title.width(), cmp::max)
+    }
+
     fn draw_columns<C: Fn(&Printer, &TableColumn, usize)>(
         &self,
         printer: &Printer,
@@ -125,13 +387,14 @@ impl SimpleTableView {
         callback: C,       
     ) {
         let mut column_offset = 0;
-        let column_count = self.columns.len();
-        for (index, column) in self.columns.iter().enumerate() {
+        let columns: Vec<(usize, &TableColumn)> = self.visible_columns().skip(self.scroll_x).collect();
+        let column_count = columns.len();
+        for (position, (index, column)) in columns.into_iter().enumerate() {
             let printer = &printer.offset((column_offset, 0)).focused(true);
 
             callback(printer, column, index);
 
-            if index < column_count - 1 {
+            if position < column_count - 1 {
                 printer.print((column.width, 0), sep);
             }
 
@@ -142,10 +405,62 @@ impl SimpleTableView {
     fn draw_item(&self, printer: &Printer, row_index: usize) {
         self.draw_columns(printer, "│", |printer, column, column_index| {
             let value = &self.rows[row_index][column_index];
-            column.draw_row(printer, value);
+            column.draw_row(printer, value, &self.search);
         });
     }
 
+    /// The column under `x`, counted from the left edge of the view.
+    fn column_at(&self, x: usize) -> Option<usize> {
+        let mut column_offset = 0;
+        for (index, column) in self.visible_columns().skip(self.scroll_x) {
+            if x < column_offset + column.width {
+                return Some(index);
+            }
+            column_offset += column.width + 1;
+        }
+
+        None
+    }
+
+    fn on_mouse(&mut self, position: Vec2, event: MouseEvent) -> EventResult {
+        match event {
+            // The focus moves along so refreshes keep the view where it is
+            MouseEvent::WheelUp | MouseEvent::WheelDown if self.is_empty() => (),
+            MouseEvent::WheelUp => {
+                self.focus_up(3);
+                self.scrollbase.scroll_to(self.focus);
+            },
+            MouseEvent::WheelDown => {
+                self.focus_down(3);
+                self.scrollbase.scroll_to(self.focus);
+            },
+            MouseEvent::Press(MouseButton::Left) if position.y == 0 => {
+                if let Some(column) = self.column_at(position.x) {
+                    self.sort_by(column);
+                }
+            },
+            MouseEvent::Press(MouseButton::Left) => {
+                let position = self.scrollbase.start_line + position.y - 1;
+                if position >= self.rows.len() {
+                    return EventResult::Consumed(None);
+                }
+                self.focus = position;
+
+                let double_click = self.last_click
+                    .is_some_and(|(last, at)| last == position && at.elapsed() < DOUBLE_CLICK);
+                self.last_click = if double_click { None } else { Some((position, Instant::now())) };
+                if let (true, Some(on_submit)) = (double_click, &self.on_submit) {
+                    let on_submit = on_submit.clone();
+                    let row = self.row_at(position);
+                    return EventResult::with_cb(move |s| on_submit(s, row));
+                }
+            },
+            _ => return EventResult::Ignored
+        }
+
+        EventResult::Consumed(None)
+    }
+
     fn focus_up(&mut self, n: usize) {
         self.focus -= cmp::min(self.focus, n);
     }
@@ -157,18 +472,24 @@ impl SimpleTableView {
 
 impl View for SimpleTableView {
     fn draw(&self, printer: &Printer) {
-        self.draw_columns(printer, "│", |printer, column, _| {
+        self.draw_columns(printer, "│", |printer, column, index| {
             let color = theme::ColorStyle::title_primary();
+            let arrow = match self.sort {
+                Some((sorted, true)) if sorted == index => " ▲",
+                Some((sorted, false)) if sorted == index => " ▼",
+                _ => ""
+            };
 
             printer.with_color(color, |printer| {
-                column.draw_header(printer);
+                column.draw_header(printer, arrow);
             });
         });
 
         let printer = &printer.offset((0, 1)).focused(true);
 
-        self.scrollbase.draw(printer, |printer, i| {
-            let style = if i == self.focus && self.enabled {
+        self.scrollbase.draw(printer, |printer, position| {
+            let i = self.row_at(position);
+            let style = if position == self.focus && self.enabled {
                 if printer.focused {
                     // Active, highlighted row
                     theme::Style::from(theme::ColorStyle::secondary()).combine(theme::Effect::Reverse)
@@ -179,10 +500,17 @@ impl View for SimpleTableView {
             } else {
                 match self.selected_rows.binary_search(&i) {
                     Ok(_) => theme::Style::from(theme::ColorStyle::secondary()).combine(theme::Effect::Bold),
+                    Err(_) if self.is_changed(i) => theme::Style::from(theme::ColorStyle::title_secondary()),
                     Err(_) => theme::Style::from(theme::ColorStyle::primary())
                 }
             };
 
+            let style = if self.marked_rows.contains(&i) {
+                style.combine(theme::Effect::Underline)
+            } else {
+                style
+            };
+
             if i < self.rows.len() {
                 printer.with_style(style, |printer| {
                     self.draw_item(printer, i);
@@ -197,18 +525,24 @@ impl View for SimpleTableView {
     }
 
     fn layout(&mut self, size: Vec2) {
-        if size == self.last_size {
+        if size == self.last_size && !self.needs_layout {
             return;
         }
 
         let item_count = self.rows.len();
-        let column_count = self.columns.len();
+        let column_count = self.visible_columns().count();
+        self.scroll_x = cmp::min(self.scroll_x, column_count.saturating_sub(1));
+        let content_widths: Vec<usize> = (0..self.columns.len())
+            .map(|index| self.content_width(index))
+            .collect();
 
-        // Split up all columns into sized / unsized groups
-        let (mut sized, mut usized): (Vec<&mut TableColumn>, Vec<&mut TableColumn>) = self
+        // Split up all shown columns into sized / unsized groups
+        let (mut sized, mut usized): (Vec<_>, Vec<_>) = self
             .columns
             .iter_mut()
-            .partition(|c| c.requested_width.is_some());
+            .enumerate()
+            .filter(|(_, c)| !c.hidden)
+            .partition(|(_, c)| c.requested_width.is_some());
 
         // Subtract one for the separators between our columns (that's column_count - 1)
         let mut available_width = size.x.saturating_sub(column_count.saturating_sub(1));
@@ -220,26 +554,47 @@ impl View for SimpleTableView {
 
         // Calculate widths for all requested columns
         let mut remaining_width = available_width;
-        for column in &mut sized {
+        for (index, column) in &mut sized {
             column.width = match *column.requested_width.as_ref().unwrap() {
                 TableColumnWidth::Percent(width) => cmp::min(
                     (size.x as f32 / 100.0 * width as f32).ceil() as usize,
                     remaining_width,
                 ),
                 TableColumnWidth::Absolute(width) => width,
+                TableColumnWidth::Content { min, max } => cmp::min(cmp::max(content_widths[*index], min), max),
             };
             remaining_width = remaining_width.saturating_sub(column.width);
         }
 
-        // Spread the remaining with across the unsized columns
+        // The flexible column takes the spare width, or gives up its own
+        // down to its minimum when the others do not fit
+        let used_width: usize = sized.iter().map(|(_, column)| column.width).sum();
+        if let Some((_, column)) = sized.iter_mut().find(|(_, column)| column.flexible) {
+            if used_width < available_width {
+                column.width += available_width - used_width;
+            } else {
+                column.width -= cmp::min(used_width - available_width, column.width.saturating_sub(column.min_width()));
+            }
+            let used_width: usize = sized.iter().map(|(_, column)| column.width).sum();
+            remaining_width = available_width.saturating_sub(used_width);
+        }
+
+        // Spread the remaining with across the unsized columns, which may
+        // push the last ones off screen instead of hiding their titles
         let remaining_columns = usized.len();
-        for column in &mut usized {
-            column.width = (remaining_width as f32 / remaining_columns as f32).floor() as usize;
+        for (_, column) in &mut usized {
+            let share = (remaining_width as f32 / remaining_columns as f32).floor() as usize;
+            column.width = cmp::max(share, column.title.len());
         }
 
         self.scrollbase
             .set_heights(size.y.saturating_sub(2), item_count);
+        // Keep the focused row on screen when the view shrinks
+        if !self.rows.is_empty() {
+            self.scrollbase.scroll_to(self.focus);
+        }
         self.last_size = size;
+        self.needs_layout = false;
     }
 
     fn take_focus(&mut self, _: Direction) -> Result<EventResult, CannotFocus> {
@@ -251,6 +606,26 @@ impl View for SimpleTableView {
             return EventResult::Ignored;
         }
 
+        if let Event::Mouse { offset, position, event } = event {
+            return match position.checked_sub(offset) {
+                Some(position) => self.on_mouse(position, event),
+                None => EventResult::Ignored
+            };
+        }
+
+        // Scroll sideways, leaving the edges to move between views
+        match event {
+            Event::Key(Key::Left) if self.scroll_x > 0 => {
+                self.scroll_x -= 1;
+                return EventResult::Consumed(None);
+            },
+            Event::Key(Key::Right) if self.overflows() => {
+                self.scroll_x += 1;
+                return EventResult::Consumed(None);
+            },
+            _ => ()
+        }
+
         let last_focus = self.focus;
         match event {
             Event::Key(Key::Up) => self.focus_up(1),
@@ -276,13 +651,17 @@ impl View for SimpleTableView {
 pub enum TableColumnWidth {
     Percent(usize),
     Absolute(usize),
+    /// As wide as the title and the widest value, within the bounds.
+    Content { min: usize, max: usize },
 }
 
 pub struct TableColumn {
     title: String,
     alignment: HAlign,
     width: usize,
-    requested_width: Option<TableColumnWidth>
+    requested_width: Option<TableColumnWidth>,
+    hidden: bool,
+    flexible: bool
 }
 
 impl TableColumn {
@@ -291,39 +670,328 @@ impl TableColumn {
             title: title.into(),
             alignment: HAlign::Left,
             width: 0,
-            requested_width
-        }
-    }
-
-    fn draw_row(&self, printer: &Printer, value: &str) {
-        let value = match self.alignment {
-            HAlign::Left => format!("{:<width$}", value, width = self.width),
-            HAlign::Right => format!("{:>width$}", value, width = self.width),
-            HAlign::Center => format!("{:^width$}", value, width = self.width),
-        };
-
-        printer.print((0, 0), value.as_str());
-    }
-
-    fn draw_header(&self, printer: &Printer) {
-        let header = match self.alignment {
-            HAlign::Left => format!(
-                "{:<width$}",
-                self.title,
-                width = self.width
-            ),
-            HAlign::Right => format!(
-                "{:>width$}",
-                self.title,
-                width = self.width
-            ),
-            HAlign::Center => format!(
-                "{:^width$}",
-                self.title,
-                width = self.width
-            ),
-        };
-
-        printer.print((0, 0), header.as_str());
+            requested_width,
+            hidden: false,
+            flexible: false
+        }
+    }
+
+    /// Lets the column take the width the others leave over.
+    pub fn flexible(mut self) -> Self {
+        self.flexible = true;
+        self
+    }
+
+    fn min_width(&self) -> usize {
+        match self.requested_width {
+            Some(TableColumnWidth::Content { min, .. }) => min,
+            _ => self.title.width()
+        }
+    }
+
+    fn draw_row(&self, printer: &Printer, value: &str, search: &str) {
+        let text = fit(value, self.width, &self.alignment);
+        printer.print((0, 0), text.as_str());
+
+        // Lowercasing may change byte offsets, in which case the match
+        // is left plain
+        let lowercase = text.to_lowercase();
+        if search.is_empty() || lowercase.len() != text.len() {
+            return;
+        }
+        if let Some(start) = lowercase.find(search) {
+            let end = start + search.len();
+            printer.with_color(theme::ColorStyle::highlight(), |printer| {
+                printer.print((text[..start].width(), 0), &text[start..end]);
+            });
+        }
+    }
+
+    fn draw_header(&self, printer: &Printer, suffix: &str) {
+        let title = format!("{}{}", self.title, suffix);
+        printer.print((0, 0), fit(&title, self.width, &self.alignment).as_str());
+    }
+}
+
+/// Compares cells as numbers when both are, and as text otherwise.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b)
+    }
+}
+
+/// Pads `text` to `width` terminal cells, or cuts it at a grapheme
+/// boundary and ends it with an ellipsis. Wide characters count twice.
+pub fn fit(text: &str, width: usize, alignment: &HAlign) -> String {
+    let text_width = text.width();
+    if text_width > width {
+        let mut cut = String::new();
+        let mut cut_width = 0;
+        for grapheme in text.graphemes(true) {
+            let grapheme_width = grapheme.width();
+            if cut_width + grapheme_width + 1 > width {
+                break;
+            }
+            cut.push_str(grapheme);
+            cut_width += grapheme_width;
+        }
+        if width > 0 {
+            cut.push('…');
+            cut_width += 1;
+        }
+
+        return format!("{}{}", cut, " ".repeat(width - cut_width));
+    }
+
+    let padding = width - text_width;
+    let (left, right) = match alignment {
+        HAlign::Left => (0, padding),
+        HAlign::Right => (padding, 0),
+        HAlign::Center => (padding / 2, padding - padding / 2)
+    };
+
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_pads_by_display_width() {
+        assert_eq!("ab  ", fit("ab", 4, &HAlign::Left));
+        assert_eq!("  ab", fit("ab", 4, &HAlign::Right));
+        assert_eq!("日本  ", fit("日本", 6, &HAlign::Left));
+    }
+
+    fn content_table() -> SimpleTableView {
+        let content = |min, max| Some(TableColumnWidth::Content { min, max });
+
+        SimpleTableView::new()
+            .columns(vec![
+                TableColumn::new("ID", content(2, 10)),
+                TableColumn::new("Project", content(3, 8)),
+                TableColumn::new("Description", content(6, 100)).flexible()
+            ])
+            .rows(vec![
+                vec!["1", "home.garden", "Water plants"],
+                vec!["12", "work", "日本語"]
+            ])
+    }
+
+    fn widths(table: &SimpleTableView) -> Vec<usize> {
+        table.columns.iter().map(|column| column.width).collect()
+    }
+
+    #[test]
+    fn content_columns_give_spare_width_to_flexible_column() {
+        let mut table = content_table();
+
+        table.layout(Vec2::new(40, 10));
+        assert_eq!(vec![2, 8, 28], widths(&table));
+
+        table.layout(Vec2::new(20, 10));
+        assert_eq!(vec![2, 8, 8], widths(&table));
+
+        table.layout(Vec2::new(12, 10));
+        assert_eq!(vec![2, 8, 6], widths(&table));
+    }
+
+    #[test]
+    fn content_columns_follow_rows_and_keep_focus() {
+        let mut table = content_table();
+        table.layout(Vec2::new(40, 10));
+        table.set_focus_row(1);
+
+        table.set_rows(vec![vec!["123", "x", "Short"], vec!["4", "y", "Other"]]);
+        table.layout(Vec2::new(40, 10));
+
+        assert_eq!(vec![3, 7, 28], widths(&table));
+        assert_eq!(Some(1), table.focus_row());
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn keyed_rows_keep_focus() {
+        let mut table = SimpleTableView::new()
+            .columns(vec![TableColumn::new("ID", None), TableColumn::new("Description", None)]);
+        table.layout(Vec2::new(40, 10));
+        table.set_keyed_rows(vec![vec!["1", "Bake"], vec!["2", "Buy"], vec!["3", "Cook"]], keys(&["a", "b", "c"]), Vec::new());
+        table.set_focus_row(2);
+
+        // The first task is done and the rest move up
+        table.set_keyed_rows(vec![vec!["1", "Cook"], vec!["2", "Buy"]], keys(&["c", "b"]), Vec::new());
+        assert_eq!(Some(0), table.focus_row());
+
+        // The focused task is gone, so the focus stays in place
+        table.set_keyed_rows(vec![vec!["1", "Buy"]], keys(&["b"]), Vec::new());
+        assert_eq!(Some(0), table.focus_row());
+    }
+
+    #[test]
+    fn keyed_rows_keep_focus_on_screen_position() {
+        let mut table = SimpleTableView::new()
+            .columns(vec![TableColumn::new("ID", None)]);
+        table.layout(Vec2::new(10, 5));
+        let rows = |count: usize| (0..count).map(|i| vec![i.to_string()]).collect::<Vec<_>>();
+        let keys = |keys: Vec<usize>| keys.into_iter().map(|k| k.to_string()).collect();
+
+        table.set_keyed_rows(rows(20), keys((0..20).collect()), Vec::new());
+        table.set_focus_row(10);
+        let offset = 10 - table.scrollbase.start_line;
+
+        // Two new tasks sort above the focused one
+        table.set_keyed_rows(rows(22), keys((100..102).chain(0..20).collect()), Vec::new());
+        assert_eq!(Some(12), table.focus_row());
+        assert_eq!(offset, 12 - table.scrollbase.start_line);
+    }
+
+    #[test]
+    fn same_columns_keep_rows_and_layout() {
+        let mut table = content_table();
+        table.layout(Vec2::new(40, 10));
+
+        table.set_columns(vec![TableColumn::new("ID", None), TableColumn::new("Project", None), TableColumn::new("Description", None)]);
+        table.set_visible_columns(&keys(&["ID", "Project", "Description"]));
+        table.set_rows(vec![vec!["1", "home.garden", "Water plants"], vec!["12", "work", "日本語"]]);
+        assert_eq!(2, table.rows.len());
+        assert!(!table.needs_layout);
+
+        table.set_visible_columns(&keys(&["ID", "Description"]));
+        assert!(table.needs_layout);
+    }
+
+    #[test]
+    fn new_columns_reset_sort_and_scroll() {
+        let mut table = content_table();
+        table.sort_by(1);
+        table.scroll_x = 5;
+
+        table.set_columns(vec![TableColumn::new("ID", None), TableColumn::new("Due", None)]);
+        assert_eq!(None, table.sort);
+        assert_eq!(0, table.scroll_x);
+    }
+
+    #[test]
+    fn keyed_rows_highlight_changes() {
+        let mut table = SimpleTableView::new()
+            .columns(vec![TableColumn::new("ID", None), TableColumn::new("Description", None)])
+            .change_highlight(Duration::from_secs(60));
+        table.set_keyed_rows(vec![vec!["1", "Bake"], vec!["2", "Buy"]], keys(&["a", "b"]), keys(&["1", "1"]));
+        assert!(!table.is_changed(0) && !table.is_changed(1));
+
+        table.set_keyed_rows(vec![vec!["1", "Bake"], vec!["2", "Buy milk"], vec!["3", "Cook"]], keys(&["a", "b", "c"]), keys(&["1", "2", "1"]));
+        assert_eq!(vec![false, true, true], (0..3).map(|i| table.is_changed(i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn keyed_rows_ignore_cells_outside_the_version() {
+        let mut table = SimpleTableView::new()
+            .columns(vec![TableColumn::new("Age", None), TableColumn::new("Description", None)])
+            .change_highlight(Duration::from_secs(60));
+        table.set_keyed_rows(vec![vec!["1min", "Bake"], vec!["2min", "Buy"]], keys(&["a", "b"]), keys(&["10", "20"]));
+
+        // Only the age of the first task moved on
+        table.set_keyed_rows(vec![vec!["2min", "Bake"], vec!["2min", "Buy milk"]], keys(&["a", "b"]), keys(&["10", "21"]));
+        assert_eq!(vec![false, true], (0..2).map(|i| table.is_changed(i)).collect::<Vec<_>>());
+    }
+
+    fn click(table: &mut SimpleTableView, x: usize, y: usize) -> EventResult {
+        table.on_event(Event::Mouse {
+            offset: Vec2::new(1, 1),
+            position: Vec2::new(x + 1, y + 1),
+            event: MouseEvent::Press(MouseButton::Left)
+        })
+    }
+
+    #[test]
+    fn header_click_sorts_and_keeps_focus() {
+        let mut table = content_table();
+        table.layout(Vec2::new(40, 10));
+        table.set_focus_row(0);
+
+        // The ID column ends at 2, the Project column starts at 3
+        click(&mut table, 4, 0);
+        assert_eq!(vec![0, 1], table.order);
+        click(&mut table, 4, 0);
+        assert_eq!(vec![1, 0], table.order);
+        assert_eq!(Some(0), table.focus_row());
+
+        // Numbers sort by value, and the order holds for new rows
+        table.sort_by(0);
+        table.set_rows(vec![vec!["10", "a", "x"], vec!["9", "b", "y"]]);
+        assert_eq!(vec![1, 0], table.order);
+    }
+
+    #[test]
+    fn wheel_moves_focus_across_refreshes() {
+        let rows: Vec<Vec<String>> = (0..20)
+            .map(|i| vec![i.to_string(), "a".to_string(), "x".to_string()])
+            .collect();
+        let keys: Vec<String> = (0..20).map(|i| format!("key{}", i)).collect();
+        let mut table = content_table();
+        table.set_keyed_rows(rows.clone(), keys.clone(), Vec::new());
+        table.layout(Vec2::new(40, 10));
+
+        for _ in 0..4 {
+            table.on_event(Event::Mouse {
+                offset: Vec2::new(0, 0),
+                position: Vec2::new(1, 1),
+                event: MouseEvent::WheelDown
+            });
+        }
+        let start_line = table.scrollbase.start_line;
+        assert_eq!(Some(12), table.focus_row());
+
+        table.set_keyed_rows(rows, keys, Vec::new());
+        assert_eq!(Some(12), table.focus_row());
+        assert_eq!(start_line, table.scrollbase.start_line);
+    }
+
+    #[test]
+    fn row_clicks_focus_and_submit() {
+        let mut table = content_table();
+        table.layout(Vec2::new(40, 10));
+        table.set_on_submit(|_, _| ());
+
+        assert!(!click(&mut table, 5, 2).has_callback());
+        assert_eq!(Some(1), table.focus_row());
+        assert!(click(&mut table, 5, 2).has_callback());
+        assert!(!click(&mut table, 5, 1).has_callback());
+        assert_eq!(Some(0), table.focus_row());
+    }
+
+    #[test]
+    fn search_jumps_between_matches() {
+        let mut table = SimpleTableView::new()
+            .columns(vec![TableColumn::new("ID", None), TableColumn::new("Description", None)])
+            .rows(vec![vec!["1", "Buy milk"], vec!["2", "Bake cake"], vec!["3", "Buy eggs"]]);
+
+        assert!(table.set_search("BUY"));
+        assert_eq!(Some(0), table.focus_row());
+        assert!(table.search_next(true));
+        assert_eq!(Some(2), table.focus_row());
+        assert!(table.search_next(true));
+        assert_eq!(Some(0), table.focus_row());
+        assert!(table.search_next(false));
+        assert_eq!(Some(2), table.focus_row());
+
+        // Every column is searched
+        assert!(table.set_search("2"));
+        assert_eq!(Some(1), table.focus_row());
+        assert!(!table.set_search("bread"));
+        assert_eq!(Some(1), table.focus_row());
+    }
+
+    #[test]
+    fn fit_truncates_with_ellipsis() {
+        assert_eq!("abc…", fit("abcdef", 4, &HAlign::Left));
+        assert_eq!("日… ", fit("日本語", 4, &HAlign::Left));
+        assert_eq!("e\u{301}…", fit("e\u{301}e\u{301}e\u{301}", 2, &HAlign::Left));
+        assert_eq!("", fit("abc", 0, &HAlign::Left));
     }
 }
\ No newline at end of file
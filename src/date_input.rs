@@ -0,0 +1,175 @@
+use std::process::Command;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, Weekday};
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{EditView, LinearLayout, TextView};
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun)
+];
+
+/// An edit field for a date with a preview of what it resolves to. The
+/// field is named `name`; read it back with `content`.
+pub fn view<F>(name: &str, width: usize, on_submit: F) -> impl View
+where
+    F: Fn(&mut Cursive) + 'static
+{
+    let preview_name = format!("{}_preview", name);
+
+    LinearLayout::horizontal()
+        .child(EditView::new()
+            .filler(" ")
+            .on_edit(move |s, text, _| {
+                let preview = preview(text);
+                s.call_on_name(&preview_name, |view: &mut TextView| view.set_content(preview));
+            })
+            .on_submit(move |s, _| on_submit(s))
+            .with_name(name)
+            .fixed_width(width))
+        .child(TextView::new("").with_name(format!("{}_preview", name)).fixed_width(22))
+}
+
+/// The resolved date of the field `name` in Taskwarrior's ISO format, or
+/// an empty string when the field is empty.
+pub fn content<'a>(s: &mut Cursive, name: &str) -> Result<String, &'a str> {
+    let text = s.call_on_name(name, |view: &mut EditView| view.get_content())
+        .map(|text| text.trim().to_string())
+        .unwrap_or_default();
+    if text.is_empty() {
+        return Ok(String::new());
+    }
+
+    resolve(&text)
+        .map(|date| date.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .ok_or("Not a date Taskwarrior understands")
+}
+
+fn preview(text: &str) -> String {
+    if text.trim().is_empty() {
+        return String::new();
+    }
+
+    match resolve(text.trim()) {
+        Some(date) => format!(" → {}", date.format("%a %Y-%m-%d %H:%M")),
+        None => " ? not a date".to_string()
+    }
+}
+
+/// Resolves a date locally when possible and asks `task calc` otherwise,
+/// e.g. for `eow`, which depends on `rc.weekstart`.
+pub fn resolve(text: &str) -> Option<NaiveDateTime> {
+    parse(text, Local::now()).or_else(|| calc(text))
+}
+
+fn calc(text: &str) -> Option<NaiveDateTime> {
+    let output = Command::new("task")
+        .arg("rc.verbose=nothing")
+        .arg("calc")
+        .arg(text)
+        .output()
+        .ok()?;
+
+    let result = String::from_utf8(output.stdout).unwrap_or_default();
+    NaiveDateTime::parse_from_str(result.trim(), "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+/// ISO dates, `now`, `today`, `tomorrow`, weekday names and durations
+/// such as `2w`, relative to `now`.
+pub fn parse(text: &str, now: DateTime<Local>) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    for format in &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(date);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(date.and_hms(0, 0, 0));
+    }
+
+    let text = text.to_lowercase();
+    let now = now.naive_local();
+    let today = now.date().and_hms(0, 0, 0);
+
+    match text.as_str() {
+        "now" => return Some(now),
+        "today" | "sod" => return Some(today),
+        "eod" => return Some(now.date().and_hms(23, 59, 59)),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        "yesterday" => return Some(today - Duration::days(1)),
+        _ => ()
+    }
+
+    // Like Taskwarrior, a weekday name is never today
+    let weekday = WEEKDAYS
+        .iter()
+        .find(|(name, _)| text.len() >= 3 && name.starts_with(text.as_str()))
+        .map(|(_, weekday)| *weekday);
+    if let Some(weekday) = weekday {
+        let from = now.weekday().num_days_from_monday() as i64;
+        let to = weekday.num_days_from_monday() as i64;
+        let days = (to - from + 6).rem_euclid(7) + 1;
+        return Some(today + Duration::days(days));
+    }
+
+    parse_duration(&text).map(|duration| now + duration)
+}
+
+fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = text.split_at(split);
+    let count: i64 = count.parse().ok()?;
+
+    match unit {
+        "min" | "mins" | "minute" | "minutes" => Some(Duration::minutes(count)),
+        "h" | "hour" | "hours" => Some(Duration::hours(count)),
+        "d" | "day" | "days" => Some(Duration::days(count)),
+        "w" | "wk" | "week" | "weeks" => Some(Duration::weeks(count)),
+        "mo" | "month" | "months" => Some(Duration::days(30 * count)),
+        "y" | "year" | "years" => Some(Duration::days(365 * count)),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Local> {
+        // A Wednesday
+        Local.from_local_datetime(&NaiveDate::from_ymd(2026, 10, 21).and_hms(15, 30, 0)).earliest().unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32, h: u32, min: u32) -> Option<NaiveDateTime> {
+        Some(NaiveDate::from_ymd(y, m, d).and_hms(h, min, 0))
+    }
+
+    #[test]
+    fn iso_dates() {
+        assert_eq!(date(2026, 11, 1, 0, 0), parse("2026-11-01", now()));
+        assert_eq!(date(2026, 11, 1, 9, 15), parse("2026-11-01T09:15", now()));
+    }
+
+    #[test]
+    fn synonyms() {
+        assert_eq!(date(2026, 10, 22, 0, 0), parse("tomorrow", now()));
+        assert_eq!(date(2026, 10, 21, 0, 0), parse("Today", now()));
+        assert_eq!(date(2026, 10, 26, 0, 0), parse("monday", now()));
+        assert_eq!(date(2026, 10, 28, 0, 0), parse("wed", now()));
+        assert_eq!(None, parse("eow", now()));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(date(2026, 11, 4, 15, 30), parse("2w", now()));
+        assert_eq!(date(2026, 10, 21, 17, 30), parse("2h", now()));
+        assert_eq!(None, parse("2x", now()));
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{Dialog, TextView};
+
+use crate::picker;
+use crate::taskwarrior::{self, TaskList};
+use crate::undo::{self, Inverse};
+
+const BLOCKED: &str = "B";
+const BLOCKING: &str = "b";
+const BOTH: &str = "Bb";
+
+/// Marks the blocked and blocking tasks of a report from the IDs each row
+/// depends on. Only tasks in the report count as blocked ones.
+pub fn mark(list: &mut TaskList, depends: &[String]) {
+    let mut blocked = Vec::new();
+    let mut blocking = Vec::new();
+    for (row, ids) in list.rows.iter().zip(depends) {
+        let ids: Vec<String> = ids
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+            .collect();
+        if !ids.is_empty() {
+            blocked.push(row.first().copied().unwrap_or_default().to_string());
+        }
+        blocking.extend(ids);
+    }
+
+    add_marker_column(list, &blocked, &blocking);
+}
+
+/// Tells in the report's "Deps" column, or in a "Dep" column added after
+/// the ID, whether each task is blocked (`B`), blocking another task (`b`)
+/// or both.
+pub fn add_marker_column(list: &mut TaskList, blocked: &[String], blocking: &[String]) {
+    if list.columns.is_empty() {
+        return;
+    }
+
+    let existing = list.column_index("Deps");
+    for row in list.rows.iter_mut() {
+        let id = row.first().copied().unwrap_or_default();
+        let is_blocked = blocked.iter().any(|b| b == id);
+        let is_blocking = blocking.iter().any(|b| b == id);
+        let marker = match (is_blocked, is_blocking) {
+            (true, true) => BOTH,
+            (true, false) => BLOCKED,
+            (false, true) => BLOCKING,
+            (false, false) => ""
+        };
+        match existing {
+            Some(index) if index < row.len() => row[index] = marker,
+            Some(_) => (),
+            None => row.insert(1, marker)
+        }
+    }
+
+    if existing.is_none() {
+        list.columns.insert(1, "Dep");
+        list.colsizes.insert(1, 3);
+    }
+}
+
+/// Lets the user pick a pending task that `task_id` will depend on.
+pub fn show_add_blocker(s: &mut Cursive, task_id: &str) {
+    let uuid = match taskwarrior::get_task_attribute(task_id, "uuid") {
+        Ok(uuid) => uuid,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let mut text = String::new();
+    let pending = match taskwarrior::get_custom_report(&mut text, "status:pending", &["id", "uuid", "description"]) {
+        Ok(pending) => pending,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let items = pending.rows
+        .iter()
+        .filter(|row| pending.cell(row, "uuid") != uuid)
+        .map(|row| (
+            format!("{:>4} {}", pending.cell(row, "id"), pending.cell(row, "description")),
+            pending.cell(row, "uuid").to_string()))
+        .collect();
+
+    picker::show(s, "Blocked by", items, move |s, blocker| {
+        change_dependency(s, &uuid, blocker, true);
+    });
+}
+
+/// Lets the user pick one of the tasks `task_id` depends on to drop.
+pub fn show_remove_blocker(s: &mut Cursive, task_id: &str) {
+    let result = taskwarrior::get_task_attribute(task_id, "uuid")
+        .and_then(|uuid| Ok((depends(&uuid)?, uuid)));
+    let (blockers, uuid) = match result {
+        Ok(found) => found,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    if blockers.is_empty() {
+        return s.add_layer(Dialog::info("This task does not depend on any other task"));
+    }
+
+    let items = blockers
+        .into_iter()
+        .map(|blocker| (describe(&blocker), blocker))
+        .collect();
+
+    picker::show(s, "No longer blocked by", items, move |s, blocker| {
+        change_dependency(s, &uuid, blocker, false);
+    });
+}
+
+/// Shows what `task_id` waits for, recursively, and what it blocks.
+pub fn show_tree(s: &mut Cursive, task_id: &str) {
+    let uuid = match taskwarrior::get_task_attribute(task_id, "uuid") {
+        Ok(uuid) => uuid,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let mut lines = render_tree(&uuid, |uuid| {
+        (describe(uuid), depends(uuid).unwrap_or_default())
+    });
+
+    let blocks = taskwarrior::get_uuids(&format!("depends:{}", uuid)).unwrap_or_default();
+    if !blocks.is_empty() {
+        lines.push(String::new());
+        lines.push("Blocks:".to_string());
+        lines.extend(blocks.iter().map(|b| format!("  {}", describe(b))));
+    }
+
+    s.add_layer(Dialog::around(TextView::new(lines.join("\n")).scrollable())
+        .title("Dependencies")
+        .dismiss_button("Close"));
+}
+
+/// Draws the dependency tree below `root`. `lookup` returns a task's
+/// description and the UUIDs it depends on; cycles are cut short.
+pub fn render_tree<F>(root: &str, lookup: F) -> Vec<String>
+where
+    F: Fn(&str) -> (String, Vec<String>)
+{
+    // Each task is looked up once, when its line is drawn
+    fn walk<F: Fn(&str) -> (String, Vec<String>)>(
+        children: &[String], prefix: &str, lookup: &F, seen: &mut HashSet<String>, lines: &mut Vec<String>
+    ) {
+        let count = children.len();
+        for (index, child) in children.iter().enumerate() {
+            let last = index + 1 == count;
+            let (description, grandchildren) = lookup(child);
+            let branch = if last { "└─ " } else { "├─ " };
+
+            if !seen.insert(child.clone()) {
+                lines.push(format!("{}{}{} (cycle)", prefix, branch, description));
+                continue;
+            }
+
+            lines.push(format!("{}{}{}", prefix, branch, description));
+            let prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+            walk(&grandchildren, &prefix, lookup, seen, lines);
+            seen.remove(child);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    seen.insert(root.to_string());
+    let (description, children) = lookup(root);
+    let mut lines = vec![description];
+    walk(&children, "", &lookup, &mut seen, &mut lines);
+
+    lines
+}
+
+fn change_dependency(s: &mut Cursive, uuid: &str, blocker: &str, add: bool) {
+    let (change, inverse) = if add {
+        (format!("depends:{}", blocker), format!("depends:-{}", blocker))
+    } else {
+        (format!("depends:-{}", blocker), format!("depends:{}", blocker))
+    };
+
+    if let Err(e) = taskwarrior::modify_task(uuid, &[&change]) {
+        return s.add_layer(Dialog::info(e));
+    }
+
+    let description = if add {
+        format!("\"{}\" blocked by \"{}\"", describe(uuid), describe(blocker))
+    } else {
+        format!("\"{}\" no longer blocked by \"{}\"", describe(uuid), describe(blocker))
+    };
+    undo::record(s, description, vec![Inverse::Modify(uuid.to_string(), vec![inverse])]);
+}
+
+fn depends<'a>(uuid: &str) -> Result<Vec<String>, &'a str> {
+    Ok(taskwarrior::get_task_attribute(uuid, "depends")?
+        .split(',')
+        .filter(|d| !d.is_empty())
+        .map(|d| d.to_string())
+        .collect())
+}
+
+fn describe(uuid: &str) -> String {
+    taskwarrior::get_task_attribute(uuid, "description").unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(uuid: &str) -> (String, Vec<String>) {
+        let depends: &[&str] = match uuid {
+            "a" => &["b", "c"],
+            "b" => &["d"],
+            "d" => &["a"],
+            _ => &[]
+        };
+        (uuid.to_uppercase(), depends.iter().map(|d| d.to_string()).collect())
+    }
+
+    #[test]
+    fn tree_with_cycle() {
+        assert_eq!(vec!["A", "├─ B", "│  └─ D", "│     └─ A (cycle)", "└─ C"], render_tree("a", lookup));
+    }
+
+    #[test]
+    fn marker_column() {
+        let mut list = TaskList {
+            colsizes: vec![2, 11],
+            columns: vec!["ID", "Description"],
+            rows: vec![vec!["1", "Buy milk"], vec!["2", "Bake cake"], vec!["3", "Eat"]]
+        };
+
+        add_marker_column(&mut list, &["2".to_string(), "3".to_string()], &["1".to_string(), "2".to_string()]);
+
+        assert_eq!(vec!["ID", "Dep", "Description"], list.columns);
+        assert_eq!(vec![2, 3, 11], list.colsizes);
+        assert_eq!(vec!["2", "Bb", "Bake cake"], list.rows[1]);
+        assert_eq!(vec!["3", "B", "Eat"], list.rows[2]);
+    }
+
+    #[test]
+    fn marks_from_depends() {
+        let mut list = TaskList {
+            colsizes: vec![2, 4, 11],
+            columns: vec!["ID", "Deps", "Description"],
+            rows: vec![vec!["1", "", "Buy milk"], vec!["2", "D", "Bake cake"], vec!["3", "D", "Eat"]]
+        };
+
+        mark(&mut list, &["".to_string(), "1".to_string(), "1 2".to_string()]);
+
+        assert_eq!(vec!["ID", "Deps", "Description"], list.columns);
+        assert_eq!(vec![vec!["1", "b", "Buy milk"], vec!["2", "Bb", "Bake cake"], vec!["3", "B", "Eat"]], list.rows);
+    }
+}
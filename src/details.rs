@@ -0,0 +1,25 @@
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{Dialog, TextView};
+
+use crate::recurrence::Recurrence;
+use crate::taskwarrior;
+
+/// Shows `task info`, preceded by the recurrence of a recurring instance.
+pub fn show(s: &mut Cursive, task_id: &str) {
+    let result = Recurrence::of(task_id)
+        .and_then(|recurrence| Ok((recurrence, taskwarrior::get_task_info(task_id)?)));
+    let (recurrence, info) = match result {
+        Ok(found) => found,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let text = match recurrence {
+        Some(recurrence) => format!("{}\n\n{}", recurrence.describe(), info.trim_end()),
+        None => info.trim_end().to_string()
+    };
+
+    s.add_layer(Dialog::around(TextView::new(text).scrollable())
+        .title(format!("Task {}", task_id))
+        .dismiss_button("Close"));
+}
@@ -0,0 +1,152 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::timesheet::json_string;
+
+const HOOK_NAME: &str = "on-modify.timewarrior";
+
+/// Who writes the Timewarrior intervals when tasks start and stop.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimewMode {
+    /// The hook when it is installed, lazytask otherwise.
+    Auto,
+    /// Always the Taskwarrior hook.
+    Hook,
+    /// Always lazytask itself.
+    Lazytask
+}
+
+impl TimewMode {
+    pub fn parse(name: &str) -> Option<TimewMode> {
+        match name.to_lowercase().as_str() {
+            "auto" => Some(TimewMode::Auto),
+            "hook" => Some(TimewMode::Hook),
+            "lazytask" => Some(TimewMode::Lazytask),
+            _ => None
+        }
+    }
+
+    pub fn uses_fallback(&self, hook_installed: bool) -> bool {
+        match self {
+            TimewMode::Auto => !hook_installed,
+            TimewMode::Hook => false,
+            TimewMode::Lazytask => true
+        }
+    }
+}
+
+pub struct Diagnostics {
+    pub task_version: Option<String>,
+    pub timew_version: Option<String>,
+    pub hook_path: PathBuf,
+    pub hook_installed: bool
+}
+
+impl Diagnostics {
+    pub fn run() -> Self {
+        let hook_path = hooks_location().join(HOOK_NAME);
+        let hook_installed = is_executable(&hook_path);
+
+        Self {
+            task_version: version("task"),
+            timew_version: version("timew"),
+            hook_path,
+            hook_installed
+        }
+    }
+
+    /// Missing binaries; lazytask cannot work without them.
+    pub fn fatal(&self) -> Option<String> {
+        match (&self.task_version, &self.timew_version) {
+            (None, _) => Some("Taskwarrior (`task`) was not found in PATH".to_string()),
+            (_, None) => Some("Timewarrior (`timew`) was not found in PATH".to_string()),
+            _ => None
+        }
+    }
+
+    pub fn hook_warning(&self) -> Option<String> {
+        if self.hook_installed {
+            None
+        } else {
+            Some(format!("The Timewarrior hook is not installed at {}", self.hook_path.display()))
+        }
+    }
+
+    pub fn report(&self, mode: TimewMode) -> String {
+        let found = |version: &Option<String>| version.clone().unwrap_or_else(|| "not found".to_string());
+        let tracking = if mode.uses_fallback(self.hook_installed) { "lazytask" } else { "hook" };
+
+        format!("task:      {}\ntimew:     {}\nhook:      {} ({})\ntracking:  {}",
+            found(&self.task_version),
+            found(&self.timew_version),
+            self.hook_path.display(),
+            if self.hook_installed { "installed" } else { "missing" },
+            tracking)
+    }
+
+    pub fn report_json(&self, mode: TimewMode) -> String {
+        let version = |v: &Option<String>| v.as_deref().map(json_string).unwrap_or_else(|| "null".to_string());
+
+        format!("{{\"task\":{},\"timew\":{},\"hook\":{},\"hook_installed\":{},\"fallback\":{}}}",
+            version(&self.task_version),
+            version(&self.timew_version),
+            json_string(&self.hook_path.to_string_lossy()),
+            self.hook_installed,
+            mode.uses_fallback(self.hook_installed))
+    }
+}
+
+fn version(binary: &str) -> Option<String> {
+    let output = Command::new(binary)
+        .arg("--version")
+        .output()
+        .ok()?;
+
+    Some(String::from_utf8(output.stdout).unwrap_or_default().trim().to_string())
+}
+
+/// Taskwarrior's `hooks.location`, which defaults to `~/.task/hooks`.
+fn hooks_location() -> PathBuf {
+    let configured = Command::new("task")
+        .arg("_get")
+        .arg("rc.hooks.location")
+        .output()
+        .map(|o| String::from_utf8(o.stdout).unwrap_or_default().trim().to_string())
+        .unwrap_or_default();
+
+    let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    match configured.strip_prefix("~/") {
+        _ if configured.is_empty() => home.join(".task").join("hooks"),
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(configured)
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &PathBuf) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or_default()
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &PathBuf) -> bool {
+    fs::metadata(path).map(|m| m.is_file()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback() {
+        assert!(TimewMode::Auto.uses_fallback(false));
+        assert!(!TimewMode::Auto.uses_fallback(true));
+        assert!(!TimewMode::Hook.uses_fallback(false));
+        assert!(TimewMode::Lazytask.uses_fallback(true));
+    }
+}
@@ -0,0 +1,133 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chrono::{DateTime, Duration, Local};
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{Dialog, EditView, OnEventView};
+
+use crate::state::AppState;
+use crate::status::Status;
+use crate::taskwarrior;
+use crate::tracking;
+use crate::undo;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Reason {
+    Threshold,
+    Midnight
+}
+
+/// Last time the user pressed a key in lazytask, shared with the event
+/// hook on the main view.
+pub type Activity = Rc<Cell<DateTime<Local>>>;
+
+/// Whether a task started at `start` looks like it was forgotten.
+pub fn detect(start: DateTime<Local>, now: DateTime<Local>, threshold: Duration, midnight: bool) -> Option<Reason> {
+    if threshold > Duration::zero() && now - start >= threshold {
+        Some(Reason::Threshold)
+    } else if midnight && start.naive_local().date() < now.naive_local().date() {
+        Some(Reason::Midnight)
+    } else {
+        None
+    }
+}
+
+/// Asks what to do about a forgotten task, once per tracked interval.
+pub fn check(s: &mut Cursive, status: &Result<Status, &str>) {
+    let (start, id, description) = match status {
+        Ok(Status { start: Some(start), id, description, .. }) => (*start, id.clone(), description.clone()),
+        _ => return
+    };
+
+    let state = s.user_data::<AppState>().expect("App state");
+    if state.idle_prompted == Some(start) {
+        return;
+    }
+
+    let threshold = Duration::hours(state.config.idle_threshold_hours as i64);
+    let reason = match detect(start, Local::now(), threshold, state.config.idle_midnight) {
+        Some(reason) => reason,
+        None => return
+    };
+
+    state.idle_prompted = Some(start);
+    let last_activity = state.last_activity.get().max(start);
+
+    let why = match reason {
+        Reason::Threshold => "for a long time",
+        Reason::Midnight => "since before midnight"
+    };
+    let stop_id = id.clone();
+    let trim_id = id.clone();
+
+    s.add_layer(Dialog::text(format!(
+            "\"{}\" has been running {} (started {}).\nLast activity in lazytask: {}.",
+            description, why, start.format("%a %H:%M"), last_activity.format("%a %H:%M")))
+        .title("Still working?")
+        .button(format!("Stop at {}", last_activity.format("%H:%M")), move |s| {
+            s.pop_layer();
+            let end = last_activity.format("%Y-%m-%dT%H:%M:%S").to_string();
+            stop_at(s, &stop_id, &end);
+        })
+        .button("Stop at…", move |s| {
+            s.pop_layer();
+            show_stop_at_dialog(s, trim_id.clone());
+        })
+        .button("Stop now", move |s| {
+            s.pop_layer();
+            tracking::stop(s, &id, |_, _| ());
+        })
+        .dismiss_button("Keep running"));
+}
+
+fn show_stop_at_dialog(s: &mut Cursive, task_id: String) {
+    s.add_layer(OnEventView::new(
+        Dialog::new()
+            .title("Stop at (e.g. 18:30 or 2020-09-03T18:30)")
+            .content(EditView::new()
+                .on_submit(move |s, end| {
+                    s.pop_layer();
+                    stop_at(s, &task_id, end);
+                })
+                .fixed_width(30))
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+/// Stops the task, then moves the end of the interval it closed back to
+/// `end`.
+fn stop_at(s: &mut Cursive, task_id: &str, end: &str) {
+    let end = end.to_string();
+    tracking::stop(s, task_id, move |s, toggle| {
+        // A resumed task opened a new interval after the closed one
+        let interval = if toggle.resumed.is_some() { "@2" } else { "@1" };
+        match taskwarrior::modify_interval_end(interval, &end) {
+            Ok(_) => undo::record(s, format!("Moved the end of {} to {}", interval, end), vec![undo::Inverse::TimewUndo]),
+            Err(e) => s.add_layer(Dialog::info(e))
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        Local.from_local_datetime(&NaiveDate::from_ymd(2020, 9, day).and_hms(hour, 0, 0)).unwrap()
+    }
+
+    #[test]
+    fn detect_forgotten() {
+        let threshold = Duration::hours(8);
+
+        assert_eq!(None, detect(at(3, 9), at(3, 16), threshold, true));
+        assert_eq!(Some(Reason::Threshold), detect(at(3, 9), at(3, 18), threshold, true));
+        assert_eq!(Some(Reason::Midnight), detect(at(3, 23), at(4, 1), threshold, true));
+        assert_eq!(None, detect(at(3, 23), at(4, 1), threshold, false));
+        assert_eq!(None, detect(at(3, 9), at(3, 18), Duration::zero(), false));
+    }
+}
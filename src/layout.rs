@@ -0,0 +1,263 @@
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::vec::Vec2;
+use cursive::view::SizeConstraint;
+use cursive::views::{BoxedView, HideableView, LinearLayout, NamedView, OnEventView, ResizedView};
+
+use crate::state::AppState;
+
+/// Below this width the intervals go under the tasks.
+const SIDE_BY_SIDE_WIDTH: usize = 140;
+/// Below this width only one pane shows at a time, the sidebar being
+/// one of the tabs.
+const STACKED_WIDTH: usize = 80;
+const SIDEBAR_WIDTH: usize = 30;
+const SPLIT_STEP: usize = 5;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Pane {
+    Tasks,
+    Intervals,
+    Sidebar
+}
+
+impl Pane {
+    fn next(self) -> Pane {
+        match self {
+            Pane::Tasks => Pane::Intervals,
+            Pane::Intervals => Pane::Sidebar,
+            Pane::Sidebar => Pane::Tasks
+        }
+    }
+}
+
+/// How the panes are arranged, kept in the app state.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Layout {
+    /// Percentage of the space the tasks get next to the intervals.
+    pub split: usize,
+    pub maximized: Option<Pane>,
+    /// The pane shown when there is only room for one.
+    pub tab: Pane,
+    applied: Option<Plan>
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            split: 60,
+            maximized: None,
+            tab: Pane::Tasks,
+            applied: None
+        }
+    }
+}
+
+/// What to show for a screen size.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Plan {
+    pub stacked: bool,
+    pub sidebar: bool,
+    pub tasks: bool,
+    pub intervals: bool,
+    /// Width, or height when stacked, of the tasks pane next to the
+    /// intervals.
+    pub tasks_size: usize
+}
+
+impl Layout {
+    pub fn plan(&self, screen: Vec2) -> Plan {
+        let stacked = screen.x < SIDE_BY_SIDE_WIDTH;
+        let single = self.maximized.or_else(|| Some(self.tab).filter(|_| screen.x < STACKED_WIDTH));
+        let space = if stacked {
+            // Leave the last line to the status bar
+            screen.y.saturating_sub(1)
+        } else {
+            screen.x.saturating_sub(SIDEBAR_WIDTH)
+        };
+
+        let shows = |pane| single.is_none_or(|single| single == pane);
+        Plan {
+            stacked,
+            sidebar: shows(Pane::Sidebar),
+            tasks: shows(Pane::Tasks),
+            intervals: shows(Pane::Intervals),
+            tasks_size: space * self.split / 100
+        }
+    }
+}
+
+/// Wraps a pane so `apply` can size and hide it by `name`.
+pub fn pane<V: View>(name: &str, view: V) -> NamedView<ResizedView<NamedView<HideableView<BoxedView>>>> {
+    HideableView::new(BoxedView::boxed(view))
+        .with_name(format!("{}_visible", name))
+        .full_screen()
+        .with_name(format!("{}_size", name))
+}
+
+/// The sidebar, tasks and intervals side by side, to be rearranged by
+/// `apply`, with the keys that change the arrangement.
+pub fn view<S: View, T: View, I: View>(sidebar: S, tasks: T, intervals: I) -> impl View {
+    OnEventView::new(LinearLayout::vertical()
+            .child(LinearLayout::horizontal()
+                .child(pane("sidebar", sidebar))
+                .child(pane("tasks", tasks))
+                .child(pane("intervals", intervals))
+                .with_name("pane_row"))
+            .with_name("panes"))
+        .on_event('[', |s| resize_split(s, false))
+        .on_event(']', |s| resize_split(s, true))
+        .on_event('M', toggle_maximized)
+        .on_event(cursive::event::Key::Tab, next_tab)
+}
+
+/// Arranges the panes for the screen size, unless they already are.
+pub fn apply(s: &mut Cursive) {
+    let screen = s.screen_size();
+    let state = s.user_data::<AppState>().expect("App state");
+    let plan = state.layout.plan(screen);
+    let applied = state.layout.applied.replace(plan);
+    if applied == Some(plan) {
+        return;
+    }
+
+    // Move the intervals between the end of the row and under it
+    let was_stacked = applied.is_some_and(|applied| applied.stacked);
+    if plan.stacked != was_stacked {
+        let intervals = if plan.stacked {
+            s.call_on_name("pane_row", |row: &mut LinearLayout| row.remove_child(2)).flatten()
+        } else {
+            s.call_on_name("panes", |panes: &mut LinearLayout| panes.remove_child(1)).flatten()
+        };
+        if let Some(intervals) = intervals {
+            let target = if plan.stacked { "panes" } else { "pane_row" };
+            s.call_on_name(target, |layout: &mut LinearLayout| layout.add_child(intervals));
+        }
+    }
+
+    let tasks_size = if plan.intervals {
+        SizeConstraint::Fixed(plan.tasks_size)
+    } else {
+        SizeConstraint::Full
+    };
+    let (tasks_width, tasks_height) = if plan.stacked {
+        (SizeConstraint::Full, tasks_size)
+    } else {
+        (tasks_size, SizeConstraint::Full)
+    };
+    let sidebar_width = if plan.tasks {
+        SizeConstraint::Fixed(SIDEBAR_WIDTH)
+    } else {
+        SizeConstraint::Full
+    };
+    resize(s, "sidebar", plan.sidebar, sidebar_width, SizeConstraint::Full);
+    resize(s, "tasks", plan.tasks, tasks_width, tasks_height);
+    resize(s, "intervals", plan.intervals, SizeConstraint::Full, SizeConstraint::Full);
+
+    // Hidden views keep the focus unless it moves
+    let shown = applied.map(|applied| (applied.tasks, applied.intervals));
+    if shown != Some((plan.tasks, plan.intervals)) {
+        let view = if plan.tasks {
+            "tasks_table"
+        } else if plan.intervals {
+            "intervals_table"
+        } else {
+            "projects_tree"
+        };
+        let _ = s.focus_name(view);
+    }
+}
+
+fn resize(s: &mut Cursive, name: &str, visible: bool, width: SizeConstraint, height: SizeConstraint) {
+    s.call_on_name(&format!("{}_visible", name), |view: &mut HideableView<BoxedView>| {
+        view.set_visible(visible);
+    });
+    s.call_on_name(&format!("{}_size", name), |view: &mut ResizedView<NamedView<HideableView<BoxedView>>>| {
+        if visible {
+            view.set_constraints(width, height);
+        } else {
+            view.set_constraints(SizeConstraint::Fixed(0), SizeConstraint::Fixed(0));
+        }
+    });
+}
+
+/// Gives the tasks more (`grow`) or less room next to the intervals.
+pub fn resize_split(s: &mut Cursive, grow: bool) {
+    let layout = &mut s.user_data::<AppState>().expect("App state").layout;
+    layout.split = if grow {
+        (layout.split + SPLIT_STEP).min(80)
+    } else {
+        layout.split.saturating_sub(SPLIT_STEP).max(20)
+    };
+
+    apply(s);
+}
+
+/// Shows only the focused pane, or all of them again.
+pub fn toggle_maximized(s: &mut Cursive) {
+    let focused = focused_pane(s);
+    let layout = &mut s.user_data::<AppState>().expect("App state").layout;
+    layout.maximized = match layout.maximized {
+        Some(_) => None,
+        None => Some(focused)
+    };
+
+    apply(s);
+}
+
+/// Switches to the next pane when only one shows.
+pub fn next_tab(s: &mut Cursive) {
+    let layout = &mut s.user_data::<AppState>().expect("App state").layout;
+    if layout.applied.is_none_or(|plan| plan.tasks && plan.intervals) {
+        return;
+    }
+    layout.tab = layout.tab.next();
+    layout.maximized = layout.maximized.map(Pane::next);
+
+    apply(s);
+}
+
+fn focused_pane(s: &mut Cursive) -> Pane {
+    let stacked = s.user_data::<AppState>().expect("App state").layout.applied.is_some_and(|plan| plan.stacked);
+    let below = s.call_on_name("panes", |panes: &mut LinearLayout| panes.get_focus_index() == 1);
+    if stacked && below.unwrap_or_default() {
+        return Pane::Intervals;
+    }
+
+    match s.call_on_name("pane_row", |row: &mut LinearLayout| row.get_focus_index()) {
+        Some(0) => Pane::Sidebar,
+        Some(2) => Pane::Intervals,
+        _ => Pane::Tasks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrangements_follow_width() {
+        let layout = Layout::default();
+
+        let wide = layout.plan(Vec2::new(180, 50));
+        assert_eq!(Plan { stacked: false, sidebar: true, tasks: true, intervals: true, tasks_size: 90 }, wide);
+
+        let narrow = layout.plan(Vec2::new(100, 51));
+        assert_eq!(Plan { stacked: true, sidebar: true, tasks: true, intervals: true, tasks_size: 30 }, narrow);
+
+        let tabbed = Layout { tab: Pane::Intervals, ..layout }.plan(Vec2::new(60, 51));
+        assert_eq!((true, false, false, true), (tabbed.stacked, tabbed.sidebar, tabbed.tasks, tabbed.intervals));
+
+        // The sidebar is the tab after the intervals
+        let sidebar = Layout { tab: Pane::Intervals.next(), ..layout }.plan(Vec2::new(60, 51));
+        assert_eq!((true, false, false), (sidebar.sidebar, sidebar.tasks, sidebar.intervals));
+    }
+
+    #[test]
+    fn maximized_pane_hides_the_others() {
+        let layout = Layout { maximized: Some(Pane::Tasks), ..Layout::default() };
+        let plan = layout.plan(Vec2::new(180, 50));
+
+        assert_eq!((false, true, false), (plan.sidebar, plan.tasks, plan.intervals));
+    }
+}
@@ -1,56 +1,124 @@
+use std::env;
+use std::fs;
+use std::process;
 use std::thread;
 use std::time::Duration;
 
 use cursive::Cursive;
+use cursive::event::EventTrigger;
 use cursive::traits::*;
-use cursive::views::{Dialog, LinearLayout, Panel, EditView, OnEventView};
+use cursive::views::{Checkbox, Dialog, LinearLayout, ListView, Panel, EditView, OnEventView, SelectView};
 
+mod calendar;
+mod charts;
+mod cli;
+mod columns;
+mod config;
 mod cursive_simple_table_view;
+mod date_input;
+mod dependencies;
+mod details;
+mod diagnostics;
+mod idle;
+mod layout;
+mod picker;
+mod pomodoro;
+mod projects;
+mod recurrence;
+mod search;
+mod snooze;
+mod state;
+mod status;
+mod status_bar;
+mod tags;
 mod taskwarrior;
+mod timesheet;
+mod tracking;
+mod undo;
 
+use config::Config;
 use cursive_simple_table_view::{SimpleTableView, TableColumn, TableColumnWidth};
+use state::AppState;
+use timesheet::{Format, RoundingMode};
+
+const MAX_COLUMN_WIDTH: usize = 40;
+const MIN_FLEXIBLE_WIDTH: usize = 20;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        process::exit(cli::run(&args));
+    }
+
+    let config = Config::load()
+        .expect("Config");
+
+    let diagnostics = diagnostics::Diagnostics::run();
+    if let Some(problem) = diagnostics.fatal() {
+        eprintln!("lazytask: {}", problem);
+        process::exit(1);
+    }
+    let timew_mode = config.timew_mode;
+    let fallback = timew_mode.uses_fallback(diagnostics.hook_installed);
+    taskwarrior::set_timew_fallback(fallback);
+    taskwarrior::set_timew_hook(diagnostics.hook_installed);
+
     let mut tasks_text = String::new();
-    let tasks = taskwarrior::get_task_list(&mut tasks_text)
+    let mut tasks = taskwarrior::get_task_list(&mut tasks_text)
         .expect("Task List");
-    let active = taskwarrior::get_active_tasks()
-        .expect("Active Tasks");
-    let tasks_columns: Vec<TableColumn> = tasks.columns
-        .into_iter()
-        .zip(tasks.colsizes)
-        .map(|(title, width)| TableColumn::new(title, Some(TableColumnWidth::Absolute(width))))
-        .collect();
+    // The first refresh fills in the markers
+    dependencies::mark(&mut tasks, &[]);
+    let active = rows_with_ids(&tasks.rows, &taskwarrior::get_active_tasks()
+        .expect("Active Tasks"));
+    let tasks_columns = table_columns(&tasks.columns, "Description");
 
     let mut intervals_text = String::new();
     let intervals = taskwarrior::get_interval_list(&mut intervals_text)
         .expect("Interval List");
-    let intervals_columns: Vec<TableColumn> = intervals.columns
-        .into_iter()
-        .zip(intervals.colsizes)
-        .map(|(title, width)| TableColumn::new(title, Some(TableColumnWidth::Absolute(width))))
-        .collect();
+    let intervals_columns = table_columns(&intervals.columns, "Tags");
 
     let mut siv = cursive::default();
 
     siv.add_global_callback(cursive::event::Key::Esc, |s : &mut Cursive| s.quit());
     siv.add_global_callback('q', |s : &mut Cursive| s.quit());
+    siv.add_global_callback('u', undo::undo_last);
+    siv.add_global_callback('g', charts::show);
     siv.load_toml(include_str!("../assets/style.toml")).unwrap();
+    let mut state = AppState::new(config);
+    state.data_dirs = taskwarrior::data_dirs();
+    let last_activity = state.last_activity.clone();
+    siv.set_user_data(state);
 
     let tasks_table = SimpleTableView::default()
         .columns(tasks_columns)
         .rows(tasks.rows)
-        .selected_rows(active);
+        .selected_rows(active)
+        .change_highlight(Duration::from_secs(3))
+        .on_submit(|s, _| task_toggle(s));
 
     let task_pane = Panel::new(
         OnEventView::new(
             tasks_table.with_name("tasks_table"))
             .on_event('a', show_add_task_dialog)
             .on_event('d', task_done)
+            .on_event('e', |s| with_focused_task(s, show_edit_dialog))
+            .on_event('i', |s| with_focused_task(s, details::show))
+            .on_event('z', |s| with_focused_task(s, snooze::show))
+            .on_event('w', snooze::toggle_waiting)
+            .on_event('C', columns::show_chooser)
             .on_event(cursive::event::Key::Del, task_delete)
             .on_event(cursive::event::Key::Backspace, task_delete)
             .on_event(cursive::event::Key::Enter, task_toggle)
             .on_event(' ', task_toggle)
+            .on_event('p', task_pomodoro)
+            .on_event('b', |s| with_focused_task(s, dependencies::show_add_blocker))
+            .on_event('B', |s| with_focused_task(s, dependencies::show_remove_blocker))
+            .on_event('D', |s| with_focused_task(s, dependencies::show_tree))
+            .on_event('m', |s| with_focused_task(s, toggle_mark))
+            .on_event('T', tags::show_browser)
+            .on_event('/', |s| search::show(s, "tasks_table"))
+            .on_event('n', |s| search::next(s, "tasks_table", true))
+            .on_event('N', |s| search::next(s, "tasks_table", false))
         ).title("Tasks");
 
     let intervals_table = SimpleTableView::default()
@@ -62,39 +130,82 @@ fn main() {
             intervals_table.with_name("intervals_table"))
             .on_event(cursive::event::Key::Del, time_delete)
             .on_event(cursive::event::Key::Backspace, time_delete)
+            .on_event('x', show_export_dialog)
+            .on_event('T', tags::show_interval_filter)
+            .on_event('/', |s| search::show(s, "intervals_table"))
+            .on_event('n', |s| search::next(s, "intervals_table", true))
+            .on_event('N', |s| search::next(s, "intervals_table", false))
         ).title("Intervals");
 
-    let view = LinearLayout::horizontal()
-        .child(task_pane.full_height().fixed_width(100))
-        .child(interval_pane.full_height().full_width());
+    let sidebar = LinearLayout::vertical()
+        .child(projects::view().full_height())
+        .child(calendar::view());
+    let panes = layout::view(sidebar, task_pane, interval_pane);
+
+    let view = OnEventView::new(LinearLayout::vertical()
+            .child(panes.full_height())
+            .child(status_bar::view()))
+        .on_pre_event_inner(EventTrigger::any(), move |_, _| {
+            last_activity.set(chrono::Local::now());
+            None
+        });
 
     siv.add_fullscreen_layer(view);
+    layout::apply(&mut siv);
+    siv.add_global_callback(cursive::event::Event::WindowResize, layout::apply);
+
+    // Nothing to warn about when lazytask was asked to do the tracking
+    let hook_warning = diagnostics.hook_warning()
+        .filter(|_| timew_mode != diagnostics::TimewMode::Lazytask);
+    if let Some(warning) = hook_warning {
+        let consequence = if fallback {
+            "lazytask will start and stop Timewarrior itself."
+        } else {
+            "Starting tasks will not record any time."
+        };
+        siv.add_layer(Dialog::info(format!("{}.\n{}", warning, consequence))
+            .title("Timewarrior hook"));
+    }
+
+    let cb_sink = siv.cb_sink().clone();
+    thread::spawn(move || {
+        loop {
+            cb_sink.send(Box::new(move |s: &mut Cursive| {
+                let result = refresh_tasks(s);
+                s.user_data::<AppState>().expect("App state").record_refresh("tasks", result);
+            })).unwrap();
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
+
+    let cb_sink = siv.cb_sink().clone();
+    thread::spawn(move || {
+        loop {
+            cb_sink.send(Box::new(move |s: &mut Cursive| {
+                let result = refresh_intervals(s);
+                s.user_data::<AppState>().expect("App state").record_refresh("intervals", result);
+            })).unwrap();
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
+
+    let cb_sink = siv.cb_sink().clone();
+    thread::spawn(move || {
+        loop {
+            cb_sink.send(Box::new(move |s: &mut Cursive| {
+                let result = projects::refresh(s);
+                s.user_data::<AppState>().expect("App state").record_refresh("projects", result);
+            })).unwrap();
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
 
     let cb_sink = siv.cb_sink().clone();
     thread::spawn(move || {
         loop {
             cb_sink.send(Box::new(move |s: &mut Cursive| {
-                let mut text = String::new();
-                let tasks = taskwarrior::get_task_list(&mut text)
-                    .expect("Task List");
-
-                s.call_on_name("tasks_table", |view: &mut SimpleTableView| {
-                    let focus_row = view.focus_row();
-                    let tasks_columns: Vec<TableColumn> = tasks.columns
-                        .into_iter()
-                        .zip(tasks.colsizes)
-                        .map(|(title, width)| TableColumn::new(title, Some(TableColumnWidth::Absolute(width))))
-                        .collect();
-                    let active = taskwarrior::get_active_tasks()
-                        .expect("Active tasks");
-
-                    view.set_columns(tasks_columns);
-                    view.set_rows(tasks.rows);
-                    view.set_selected_rows(active);
-                    if focus_row.is_some() {
-                        view.set_focus_row(focus_row.unwrap());
-                    }
-                });
+                let result = calendar::refresh(s);
+                s.user_data::<AppState>().expect("App state").record_refresh("calendar", result);
             })).unwrap();
             thread::sleep(Duration::from_secs(1));
         }
@@ -103,24 +214,11 @@ fn main() {
     let cb_sink = siv.cb_sink().clone();
     thread::spawn(move || {
         loop {
+            let status = status::current();
             cb_sink.send(Box::new(move |s: &mut Cursive| {
-                let mut text = String::new();
-                let intervals = taskwarrior::get_interval_list(&mut text)
-                    .expect("Intervals List");
-
-                s.call_on_name("intervals_table", |view: &mut SimpleTableView| {
-                    let focus_row = view.focus_row();
-                    let intervals_columns: Vec<TableColumn> = intervals.columns
-                        .into_iter()
-                        .zip(intervals.colsizes)
-                        .map(|(title, width)| TableColumn::new(title, Some(TableColumnWidth::Absolute(width))))
-                        .collect();
-                    view.set_columns(intervals_columns);
-                    view.set_rows(intervals.rows);
-                    if focus_row.is_some() {
-                        view.set_focus_row(focus_row.unwrap());
-                    }
-                });
+                pomodoro::tick(s);
+                idle::check(s, &status);
+                status_bar::update(s, status);
             })).unwrap();
             thread::sleep(Duration::from_secs(1));
         }
@@ -129,57 +227,338 @@ fn main() {
     siv.run();
 }
 
+fn refresh_tasks(s: &mut Cursive) -> Result<(), &'static str> {
+    let (report, filter, visible, layout) = {
+        let state = s.user_data::<AppState>().expect("App state");
+        (state.report.clone(),
+            state.filter(),
+            state.config.columns.get(&state.report).cloned(),
+            state.report_layouts.get(&state.report).cloned())
+    };
+    let layout = match layout {
+        Some(layout) => layout,
+        None => {
+            let layout = taskwarrior::get_report_layout(&report)?;
+            s.user_data::<AppState>().expect("App state").report_layouts.insert(report.clone(), layout.clone());
+            layout
+        }
+    };
+
+    let mut text = String::new();
+    let keyed = taskwarrior::get_keyed_task_report(&mut text, &report, &layout, &filter)?;
+    let (mut tasks, uuids, modified) = (keyed.list, keyed.uuids, keyed.modified);
+    dependencies::mark(&mut tasks, &keyed.depends);
+    let active = rows_with_ids(&tasks.rows, &taskwarrior::get_active_tasks()
+        .map_err(|_| "Could not list active tasks")?);
+    let marked: Vec<usize> = {
+        let state = s.user_data::<AppState>().expect("App state");
+        uuids.iter()
+            .enumerate()
+            .filter(|(_, uuid)| state.marked.contains(*uuid))
+            .map(|(index, _)| index)
+            .collect()
+    };
+
+    s.call_on_name("tasks_table", |view: &mut SimpleTableView| {
+        view.set_columns(table_columns(&tasks.columns, "Description"));
+        // Reports without a choice show all their columns
+        let visible = visible.unwrap_or_else(|| tasks.columns.iter().map(|title| title.to_string()).collect());
+        view.set_visible_columns(&visible);
+        view.set_keyed_rows(tasks.rows, uuids, modified);
+        view.set_selected_rows(active);
+        view.set_marked_rows(marked);
+    });
+
+    Ok(())
+}
+
+/// Columns as wide as their content. The `flexible` one takes the spare
+/// width and is the first to shrink when the pane gets narrow.
+fn table_columns(titles: &[&str], flexible: &str) -> Vec<TableColumn> {
+    titles.iter()
+        .map(|title| {
+            let width = |min| Some(TableColumnWidth::Content { min, max: MAX_COLUMN_WIDTH });
+            if *title == flexible {
+                TableColumn::new(*title, width(MIN_FLEXIBLE_WIDTH)).flexible()
+            } else {
+                TableColumn::new(*title, width(title.len()))
+            }
+        })
+        .collect()
+}
+
+/// Indices of the rows whose ID is one of `ids`.
+fn rows_with_ids(rows: &[Vec<&str>], ids: &[String]) -> Vec<usize> {
+    rows.iter()
+        .enumerate()
+        .filter(|(_, row)| row.first().is_some_and(|id| ids.iter().any(|i| i == id)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn refresh_intervals(s: &mut Cursive) -> Result<(), &'static str> {
+    let filter = s.user_data::<AppState>().expect("App state").interval_filter();
+
+    let mut text = String::new();
+    let intervals = taskwarrior::get_interval_list_for(&mut text, &filter)?;
+
+    s.call_on_name("intervals_table", |view: &mut SimpleTableView| {
+        let focus_row = view.focus_row();
+        view.set_columns(table_columns(&intervals.columns, "Tags"));
+        view.set_rows(intervals.rows);
+        if focus_row.is_some() {
+            view.set_focus_row(focus_row.unwrap());
+        }
+    });
+
+    Ok(())
+}
+
 fn show_add_task_dialog(s: &mut Cursive) {
-    s.add_layer(Dialog::new()
-        .title("Add Task")
-        .content(
-            OnEventView::new(
-                EditView::new()
-                    .filler(" ")
-                    .on_submit(cb_task_add)
-                    .with_name("new_task_name")
-                    .fixed_width(50)
-            ).on_event(cursive::event::Key::Esc, cancel_dialog))
-        .dismiss_button("Cancel")
-    )
-}
-
-fn cb_task_add(s: &mut Cursive, text: &str) {
-    taskwarrior::add_task(text)
-        .expect("Add task");
+    let field = |name: &str, width: usize| EditView::new()
+        .filler(" ")
+        .on_submit(|s, _| cb_task_add(s))
+        .with_name(name)
+        .fixed_width(width);
+
+    s.add_layer(OnEventView::new(
+        Dialog::new()
+            .title("Add Task")
+            .content(ListView::new()
+                .child("Description", field("new_task_name", 50))
+                .child("Due", date_input::view("new_task_due", 20, cb_task_add))
+                .child("Recur", field("new_task_recur", 20))
+                .child("Until", date_input::view("new_task_until", 20, cb_task_add)))
+            .button("Add", cb_task_add)
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, cancel_dialog));
+}
+
+fn cb_task_add(s: &mut Cursive) {
+    let mut field = |name: &str| s.call_on_name(name, |view: &mut EditView| view.get_content())
+        .map(|text| text.trim().to_string())
+        .unwrap_or_default();
+    let text = field("new_task_name");
+    let recur = field("new_task_recur");
+    if text.is_empty() {
+        return;
+    }
+
+    let dates = date_input::content(s, "new_task_due")
+        .and_then(|due| Ok((due, date_input::content(s, "new_task_until")?)));
+    let args = dates.and_then(|(due, until)| recurrence::add_args(&due, &recur, &until));
+    let args = match args {
+        Ok(args) => args,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    // A refused task keeps the dialog open to fix it
+    if let Err(e) = taskwarrior::add_task(&text, &args) {
+        return s.add_layer(Dialog::info(e));
+    }
+    undo::record(s, format!("Added \"{}\"", text), undo::task_change(0));
     s.pop_layer();
 }
 
-fn task_toggle(s: &mut Cursive) {
-    s.call_on_name("tasks_table", |view: &mut SimpleTableView| {
-        let task_id = match view.focus_row() {
-            Some(index) => index,
-            None => {
-                return ();
-            }
-        };
+/// Edits the description of a task.
+fn show_edit_dialog(s: &mut Cursive, task_id: &str) {
+    let description = taskwarrior::get_task_attribute(task_id, "description")
+        .unwrap_or_default();
 
-        let active = taskwarrior::get_active_tasks()
-            .expect("Active tasks");
-        active.iter()
-            .for_each(|index| {
-                taskwarrior::stop_task(&(index + 1).to_string())
-                    .expect("Stop task");
-            });
+    let submit = (task_id.to_string(), description.clone());
+    let button = submit.clone();
+    s.add_layer(OnEventView::new(
+        Dialog::new()
+            .title(format!("Edit Task {}", task_id))
+            .content(EditView::new()
+                .content(description)
+                .filler(" ")
+                .on_submit(move |s, _| cb_task_edit(s, &submit.0, &submit.1))
+                .with_name("edit_task")
+                .fixed_width(60))
+            .button("Save", move |s| cb_task_edit(s, &button.0, &button.1))
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, cancel_dialog));
+}
 
-        if active.len() == 1 && active.contains(&task_id) {
-            return;
+fn cb_task_edit(s: &mut Cursive, task_id: &str, description: &str) {
+    let text = s.call_on_name("edit_task", |view: &mut EditView| view.get_content())
+        .expect("Edit task");
+    s.pop_layer();
+    let text = text.trim();
+    if text.is_empty() || text == description {
+        return;
+    }
+
+    let recurring = match recurrence::Recurrence::of(task_id) {
+        Ok(recurring) => recurring,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let task_id = task_id.to_string();
+    let change = format!("description:{}", text);
+    let edit = move |s: &mut Cursive, series: bool, inverse: Vec<undo::Inverse>| {
+        if let Err(e) = taskwarrior::modify_task_series(&task_id, &[&change], series) {
+            return s.add_layer(Dialog::info(e));
         }
+        undo::record(s, format!("Edited task {}", task_id), inverse);
+    };
 
-        taskwarrior::start_task(&(task_id + 1).to_string())
-            .expect("Task start");
-    });
+    match recurring {
+        Some(recurrence) => {
+            let steps = (recurrence.undo_steps(false, 0), recurrence.undo_steps(true, 0));
+            recurrence::choose(s, "Edit", &recurrence, move |s, series| {
+                edit(s, series, if series { steps.1.clone() } else { steps.0.clone() });
+            });
+        },
+        None => edit(s, false, undo::task_change(0))
+    }
+}
+
+fn show_export_dialog(s: &mut Cursive) {
+    let state = s.user_data::<AppState>().expect("App state");
+    let format = state.config.export_format;
+    let rounding = state.config.export_rounding;
+    let range = state.interval_filter();
+
+    let formats = SelectView::new()
+        .popup()
+        .item("CSV", Format::Csv)
+        .item("JSON", Format::Json)
+        .item("Markdown", Format::Markdown)
+        .selected(match format {
+            Format::Csv => 0,
+            Format::Json => 1,
+            Format::Markdown => 2
+        })
+        .on_submit(|s: &mut Cursive, format: &Format| {
+            // Keep a path the user typed
+            s.call_on_name("export_path", |view: &mut EditView| {
+                let defaults = [Format::Csv, Format::Json, Format::Markdown]
+                    .iter()
+                    .map(|format| format!("timesheet.{}", format.extension()))
+                    .collect::<Vec<_>>();
+                if defaults.contains(&view.get_content().to_string()) {
+                    view.set_content(format!("timesheet.{}", format.extension()));
+                }
+            });
+        });
+
+    let modes = SelectView::new()
+        .popup()
+        .item("Nearest", RoundingMode::Nearest)
+        .item("Up", RoundingMode::Up)
+        .item("Down", RoundingMode::Down)
+        .selected(match rounding.mode {
+            RoundingMode::Nearest => 0,
+            RoundingMode::Up => 1,
+            RoundingMode::Down => 2
+        });
+
+    s.add_layer(OnEventView::new(
+        Dialog::new()
+            .title("Export Intervals")
+            .content(ListView::new()
+                .child("Range", EditView::new()
+                    .content(range)
+                    .with_name("export_range")
+                    .fixed_width(30))
+                .child("Format", formats.with_name("export_format"))
+                .child("File", EditView::new()
+                    .content(format!("timesheet.{}", format.extension()))
+                    .with_name("export_path")
+                    .fixed_width(30))
+                .child("Round (min)", EditView::new()
+                    .content(rounding.minutes.to_string())
+                    .with_name("export_round")
+                    .fixed_width(5))
+                .child("Rounding", modes.with_name("export_rounding"))
+                .child("Totals only", Checkbox::new().with_name("export_totals")))
+            .button("Export", cb_export)
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, cancel_dialog));
+}
+
+fn cb_export(s: &mut Cursive) {
+    let format = s.call_on_name("export_format", |view: &mut SelectView<Format>| {
+        view.selection().map(|f| *f)
+    }).flatten().unwrap_or(Format::Csv);
+    let mode = s.call_on_name("export_rounding", |view: &mut SelectView<RoundingMode>| {
+        view.selection().map(|m| *m)
+    }).flatten().unwrap_or(RoundingMode::Nearest);
+    let path = s.call_on_name("export_path", |view: &mut EditView| view.get_content())
+        .expect("Export path");
+    let range = s.call_on_name("export_range", |view: &mut EditView| view.get_content())
+        .expect("Export range");
+    let minutes = s.call_on_name("export_round", |view: &mut EditView| view.get_content())
+        .expect("Export rounding")
+        .parse::<u64>()
+        .unwrap_or_default();
+    let totals = s.call_on_name("export_totals", |view: &mut Checkbox| view.is_checked())
+        .unwrap_or_default();
+
+    let rounding = timesheet::Rounding { minutes, mode };
+    let result = timesheet::export(range.trim(), format, &rounding, totals)
+        .and_then(|text| fs::write(path.as_str(), text).map_err(|_| "Could not write export file"));
+
+    s.pop_layer();
+    match result {
+        Ok(()) => s.add_layer(Dialog::info(format!("Exported to {}", path))),
+        Err(e) => s.add_layer(Dialog::info(e))
+    }
+}
+
+fn task_toggle(s: &mut Cursive) {
+    with_focused_task(s, |s, task_id| tracking::toggle(s, task_id, |_, _| ()));
+}
+
+fn task_pomodoro(s: &mut Cursive) {
+    with_focused_task(s, pomodoro::toggle);
+}
+
+/// Marks the task for bulk changes, or unmarks it.
+fn toggle_mark(s: &mut Cursive, task_id: &str) {
+    let uuid = match taskwarrior::get_task_attribute(task_id, "uuid") {
+        Ok(uuid) => uuid,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let state = s.user_data::<AppState>().expect("App state");
+    if !state.marked.remove(&uuid) {
+        state.marked.insert(uuid);
+    }
+}
+
+/// Runs `action` with the ID of the focused task, if there is one.
+fn with_focused_task<F: Fn(&mut Cursive, &str)>(s: &mut Cursive, action: F) {
+    if let Some(task_id) = focused_cell(s, "tasks_table", 0) {
+        action(s, &task_id);
+    }
+}
+
+/// Text of a column in the focused row of a table.
+fn focused_cell(s: &mut Cursive, table: &str, column: usize) -> Option<String> {
+    s.call_on_name(table, |view: &mut SimpleTableView| {
+        let index = view.focus_row()?;
+        view.borrow_row(index)
+            .and_then(|row| row.get(column).cloned())
+    }).flatten()
 }
 
 fn task_delete(s: &mut Cursive) {
+    let recurring = focused_cell(s, "tasks_table", 0)
+        .and_then(|task_id| recurrence::Recurrence::of(&task_id).ok().flatten());
+    if let Some(recurrence) = recurring {
+        return recurrence::choose(s, "Delete", &recurrence, cb_delete_task);
+    }
+
     s.add_layer(OnEventView::new(
         Dialog::text("Are you sure?")
-            .button("Ok", cb_delete_task)
+            .button("Ok", |s| {
+                s.pop_layer();
+                cb_delete_task(s, false);
+            })
             .dismiss_button("Cancel"))
         .on_event(cursive::event::Key::Esc, cancel_dialog));
 }
@@ -192,54 +571,43 @@ fn time_delete(s: &mut Cursive) {
         .on_event(cursive::event::Key::Esc, cancel_dialog));
 }
 
-fn cb_delete_task(s: &mut Cursive) {
-    s.call_on_name("tasks_table", |view: &mut SimpleTableView| {
-        match view.focus_row() {
-            Some(index) => {
-                let task_id = view.borrow_row(index)
-                    .expect("Highlighted row")
-                    .get(0)
-                    .expect("Highlighted 0 cell");
-                taskwarrior::delete_task(task_id)
-                    .expect("Delete task");
+fn cb_delete_task(s: &mut Cursive, series: bool) {
+    if let Some(task_id) = focused_cell(s, "tasks_table", 0) {
+        let description = taskwarrior::get_task_attribute(&task_id, "description")
+            .unwrap_or_default();
+        let recurrence = recurrence::Recurrence::of(&task_id).ok().flatten();
+        match taskwarrior::delete_task(&task_id, series) {
+            Ok(timew) => {
+                let inverse = match recurrence {
+                    Some(recurrence) => recurrence.undo_steps(series, timew),
+                    None => undo::task_change(timew)
+                };
+                undo::record(s, format!("Deleted \"{}\"", description), inverse);
             },
-            None => {()}
+            Err(e) => s.add_layer(Dialog::info(e))
         }
-    });
-    s.pop_layer();
+    }
 }
 
 fn cb_delete_time(s: &mut Cursive) {
-    s.call_on_name("intervals_table", |view: &mut SimpleTableView| {
-        match view.focus_row() {
-            Some(index) => {
-                let interval_id = view.borrow_row(index)
-                    .expect("Highlighted row")
-                    .get(3)
-                    .expect("Highlighted 0 cell");
-                taskwarrior::delete_time(interval_id)
-                    .expect("Delete interval");
-            },
-            None => {()}
-        }
-    });
     s.pop_layer();
+    if let Some(interval_id) = focused_cell(s, "intervals_table", 3) {
+        match taskwarrior::delete_time(&interval_id) {
+            Ok(_) => undo::record(s, format!("Deleted interval {}", interval_id), vec![undo::Inverse::TimewUndo]),
+            Err(e) => s.add_layer(Dialog::info(e))
+        }
+    }
 }
 
 fn task_done(s: &mut Cursive) {
-    s.call_on_name("tasks_table", |view: &mut SimpleTableView| {
-        match view.focus_row() {
-            Some(index) => {
-                let task_id = view.borrow_row(index)
-                    .expect("Highlighted row")
-                    .get(0)
-                    .expect("Highlighted 0 cell");
-                taskwarrior::done_task(task_id)
-                    .expect("Add task");
-            },
-            None => {()}
+    if let Some(task_id) = focused_cell(s, "tasks_table", 0) {
+        let description = taskwarrior::get_task_attribute(&task_id, "description")
+            .unwrap_or_default();
+        match taskwarrior::done_task(&task_id) {
+            Ok(timew) => undo::record(s, format!("Completed \"{}\"", description), undo::task_change(timew)),
+            Err(e) => s.add_layer(Dialog::info(e))
         }
-    });
+    }
 }
 
 fn cancel_dialog(s: &mut Cursive) {
@@ -0,0 +1,55 @@
+use std::rc::Rc;
+
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, SelectView};
+
+/// Shows `items` (label, value) in a list narrowed down by typing, and
+/// calls `on_pick` with the value of the chosen item.
+pub fn show<F>(s: &mut Cursive, title: &str, items: Vec<(String, String)>, on_pick: F)
+where
+    F: Fn(&mut Cursive, &str) + 'static
+{
+    let items = Rc::new(items);
+    let on_pick = Rc::new(on_pick);
+
+    let mut list = SelectView::<String>::new();
+    list.add_all(items.iter().cloned());
+
+    let submit_pick = on_pick.clone();
+    let list = list.on_submit(move |s: &mut Cursive, value: &String| {
+        s.pop_layer();
+        on_pick(s, value);
+    });
+
+    let search = EditView::new()
+        .on_edit(move |s, query, _| {
+            let query = query.to_lowercase();
+            s.call_on_name("picker_list", |list: &mut SelectView<String>| {
+                list.clear();
+                list.add_all(items
+                    .iter()
+                    .filter(|(label, _)| label.to_lowercase().contains(&query))
+                    .cloned());
+            });
+        })
+        .on_submit(move |s, _| {
+            let value = s.call_on_name("picker_list", |list: &mut SelectView<String>| list.selection())
+                .flatten();
+            if let Some(value) = value {
+                s.pop_layer();
+                submit_pick(s, &value);
+            }
+        });
+
+    s.add_layer(OnEventView::new(
+        Dialog::new()
+            .title(title)
+            .content(LinearLayout::vertical()
+                .child(search.fixed_width(60))
+                .child(list.with_name("picker_list").scrollable().fixed_height(15)))
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
@@ -0,0 +1,242 @@
+use std::time::{Duration, Instant};
+
+use cursive::Cursive;
+use cursive::views::Dialog;
+
+use crate::state::AppState;
+use crate::taskwarrior;
+use crate::tracking;
+use crate::undo;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LogMode {
+    Annotation,
+    Tag
+}
+
+impl LogMode {
+    pub fn parse(name: &str) -> Option<LogMode> {
+        match name.to_lowercase().as_str() {
+            "annotation" => Some(LogMode::Annotation),
+            "tag" => Some(LogMode::Tag),
+            _ => None
+        }
+    }
+}
+
+/// Pomodoro lengths in minutes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Durations {
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    pub long_break_every: u32
+}
+
+impl Default for Durations {
+    fn default() -> Self {
+        Self { work: 25, short_break: 5, long_break: 15, long_break_every: 4 }
+    }
+}
+
+impl Durations {
+    pub fn of(&self, phase: Phase) -> Duration {
+        let minutes = match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break
+        };
+
+        Duration::from_secs(minutes * 60)
+    }
+
+    /// The break that follows the `completed`-th pomodoro.
+    pub fn break_after(&self, completed: u32) -> Phase {
+        if self.long_break_every > 0 && completed.is_multiple_of(self.long_break_every) {
+            Phase::LongBreak
+        } else {
+            Phase::ShortBreak
+        }
+    }
+}
+
+/// A running work session or break. The task is kept by UUID because its
+/// ID may change while the pomodoro runs.
+pub struct Pomodoro {
+    pub task_uuid: String,
+    pub description: String,
+    pub phase: Phase,
+    pub ends_at: Instant
+}
+
+impl Pomodoro {
+    pub fn new(task_uuid: String, description: String, phase: Phase, length: Duration) -> Self {
+        Self { task_uuid, description, phase, ends_at: Instant::now() + length }
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.ends_at.saturating_duration_since(now)
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now >= self.ends_at
+    }
+
+    /// Countdown text for the status line, e.g. `work 24:59`.
+    pub fn label(&self, now: Instant) -> String {
+        let name = match self.phase {
+            Phase::Work => "work",
+            Phase::ShortBreak => "break",
+            Phase::LongBreak => "long break"
+        };
+        let seconds = self.remaining(now).as_secs();
+
+        format!("{} {:02}:{:02}", name, seconds / 60, seconds % 60)
+    }
+}
+
+/// Starts a pomodoro on `task_id`, or cancels the one that is running.
+pub fn toggle(s: &mut Cursive, task_id: &str) {
+    let running = s.user_data::<AppState>().expect("App state").pomodoro.take();
+    if let Some(pomodoro) = running {
+        if pomodoro.phase == Phase::Work {
+            tracking::stop(s, &pomodoro.task_uuid, |_, _| ());
+        }
+        return;
+    }
+
+    let result = taskwarrior::get_task_attribute(task_id, "uuid")
+        .and_then(|uuid| {
+            let description = taskwarrior::get_task_attribute(&uuid, "description")?;
+            Ok((uuid, description))
+        });
+
+    match result {
+        Ok((uuid, description)) => start_work(s, uuid, description),
+        Err(e) => s.add_layer(Dialog::info(e))
+    }
+}
+
+/// Advances the running pomodoro; called once a second.
+pub fn tick(s: &mut Cursive) {
+    let state = s.user_data::<AppState>().expect("App state");
+    let finished = match &state.pomodoro {
+        Some(pomodoro) => pomodoro.is_finished(Instant::now()),
+        None => false
+    };
+
+    if !finished {
+        return;
+    }
+
+    let pomodoro = state.pomodoro.take().expect("Pomodoro");
+    match pomodoro.phase {
+        Phase::Work => {
+            state.pomodoros_completed += 1;
+            let completed = state.pomodoros_completed;
+            let durations = state.config.pomodoro_durations;
+            let log = state.config.pomodoro_log;
+            let phase = durations.break_after(completed);
+            let Pomodoro { task_uuid, description, .. } = pomodoro;
+
+            let uuid = task_uuid.clone();
+            let finish = move |s: &mut Cursive, _: &taskwarrior::Toggle| {
+                if let Err(e) = log_completed(&task_uuid, log, durations.work) {
+                    s.add_layer(Dialog::info(e));
+                    return;
+                }
+                undo::record(s, format!("Logged pomodoro on \"{}\"", description), vec![undo::Inverse::TaskUndo]);
+
+                let task_uuid = task_uuid.clone();
+                let description = description.clone();
+                s.add_layer(Dialog::text(format!(
+                        "Pomodoro #{} on \"{}\" is done. Take a break?", completed, description))
+                    .title("Pomodoro")
+                    .button("Break", move |s| {
+                        s.pop_layer();
+                        let length = s.user_data::<AppState>().expect("App state")
+                            .config.pomodoro_durations.of(phase);
+                        s.user_data::<AppState>().expect("App state").pomodoro =
+                            Some(Pomodoro::new(task_uuid.clone(), description.clone(), phase, length));
+                    })
+                    .dismiss_button("Skip"));
+            };
+
+            // The task may have been stopped by hand during the pomodoro
+            if tracking::is_tracked(&uuid) {
+                tracking::toggle(s, &uuid, finish);
+            } else {
+                finish(s, &taskwarrior::Toggle::default());
+            }
+        },
+        Phase::ShortBreak | Phase::LongBreak => {
+            let Pomodoro { task_uuid, description, .. } = pomodoro;
+            s.add_layer(Dialog::text(format!("Break is over. Back to \"{}\"?", description))
+                .title("Pomodoro")
+                .button("Start", move |s| {
+                    s.pop_layer();
+                    start_work(s, task_uuid.clone(), description.clone());
+                })
+                .dismiss_button("Later"));
+        }
+    }
+}
+
+/// Starts the task through the active-task policy, then the work session.
+fn start_work(s: &mut Cursive, uuid: String, description: String) {
+    if tracking::is_tracked(&uuid) {
+        begin_work(s, uuid, description);
+        return;
+    }
+
+    let task_uuid = uuid.clone();
+    tracking::toggle(s, &uuid, move |s, toggle| {
+        if toggle.started.is_some() {
+            begin_work(s, task_uuid.clone(), description.clone());
+        }
+    });
+}
+
+fn begin_work(s: &mut Cursive, uuid: String, description: String) {
+    let state = s.user_data::<AppState>().expect("App state");
+    let length = state.config.pomodoro_durations.of(Phase::Work);
+    state.pomodoro = Some(Pomodoro::new(uuid, description, Phase::Work, length));
+}
+
+fn log_completed<'a>(uuid: &str, log: LogMode, minutes: u64) -> Result<(), &'a str> {
+    match log {
+        LogMode::Annotation => taskwarrior::annotate_task(uuid, &format!("Pomodoro completed ({} min)", minutes)),
+        LogMode::Tag => taskwarrior::modify_task(uuid, &["+pomodoro"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaks() {
+        let durations = Durations::default();
+
+        assert_eq!(Phase::ShortBreak, durations.break_after(1));
+        assert_eq!(Phase::LongBreak, durations.break_after(4));
+        assert_eq!(Duration::from_secs(900), durations.of(Phase::LongBreak));
+    }
+
+    #[test]
+    fn countdown() {
+        let pomodoro = Pomodoro::new("uuid".to_string(), "Write".to_string(), Phase::Work, Duration::from_secs(90));
+        let now = Instant::now();
+
+        assert!(!pomodoro.is_finished(now));
+        assert_eq!("work 01:30", pomodoro.label(pomodoro.ends_at - Duration::from_secs(90)));
+        assert!(pomodoro.is_finished(pomodoro.ends_at));
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{OnEventView, Panel, SelectView};
+
+use crate::state::AppState;
+use crate::taskwarrior;
+use crate::timesheet;
+
+/// A project and everything below it; `count` and `seconds` include the
+/// subprojects.
+#[derive(Default, PartialEq, Debug)]
+pub struct Node {
+    pub name: String,
+    pub path: String,
+    pub count: usize,
+    pub seconds: u64,
+    pub children: BTreeMap<String, Node>
+}
+
+impl Node {
+    fn add(&mut self, path: &str, count: usize, seconds: u64) {
+        self.count += count;
+        self.seconds += seconds;
+
+        let mut node = self;
+        let mut prefix = String::new();
+        for name in path.split('.') {
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(name);
+            node = node.children.entry(name.to_string()).or_insert_with(|| Node {
+                name: name.to_string(),
+                path: prefix.clone(),
+                ..Node::default()
+            });
+            node.count += count;
+            node.seconds += seconds;
+        }
+    }
+}
+
+/// Builds the tree of dotted project names. `counts` and `seconds` hold the
+/// pending tasks and tracked time of each project on its own.
+pub fn build(projects: &[String], counts: &BTreeMap<String, usize>, seconds: &BTreeMap<String, u64>) -> Node {
+    let mut names: BTreeSet<&str> = projects.iter().map(|p| p.as_str()).collect();
+    names.extend(counts.keys().map(|p| p.as_str()));
+
+    let mut root = Node::default();
+    for name in names {
+        root.add(name,
+            counts.get(name).copied().unwrap_or_default(),
+            seconds.get(name).copied().unwrap_or_default());
+    }
+
+    root
+}
+
+/// One list entry per visible project, indented by depth, with the
+/// project name as the value.
+pub fn lines(root: &Node, collapsed: &BTreeSet<String>) -> Vec<(String, String)> {
+    fn walk(node: &Node, depth: usize, collapsed: &BTreeSet<String>, lines: &mut Vec<(String, String)>) {
+        for child in node.children.values() {
+            let is_collapsed = collapsed.contains(&child.path);
+            let marker = match (child.children.is_empty(), is_collapsed) {
+                (true, _) => " ",
+                (false, true) => "▸",
+                (false, false) => "▾"
+            };
+            let label = format!("{}{} {} ({}) {}",
+                "  ".repeat(depth),
+                marker,
+                child.name,
+                child.count,
+                timesheet::format_duration(child.seconds));
+            lines.push((label, child.path.clone()));
+
+            if !is_collapsed {
+                walk(child, depth + 1, collapsed, lines);
+            }
+        }
+    }
+
+    let mut lines = vec![(format!("All ({})", root.count), String::new())];
+    walk(root, 0, collapsed, &mut lines);
+
+    lines
+}
+
+pub fn view() -> impl View {
+    Panel::new(
+        OnEventView::new(
+            SelectView::<String>::new()
+                .on_submit(select)
+                .with_name("projects_tree"))
+            .on_event(cursive::event::Key::Left, |s| set_collapsed(s, true))
+            .on_event(cursive::event::Key::Right, |s| set_collapsed(s, false))
+        ).title("Projects")
+}
+
+/// Reloads the projects when the data changed, and every minute for the
+/// time tracked since.
+pub fn refresh(s: &mut Cursive) -> Result<(), &'static str> {
+    let state = s.user_data::<AppState>().expect("App state");
+    let key = format!("{} {:?}",
+        chrono::Local::now().format("%F %R"),
+        taskwarrior::data_modified(&state.data_dirs));
+    if state.loaded.get("projects") == Some(&key) {
+        return Ok(());
+    }
+
+    let projects = taskwarrior::get_projects()?;
+
+    let mut text = String::new();
+    let pending = taskwarrior::get_custom_report(&mut text, "status:pending", &["uuid", "project"])?;
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for row in pending.rows.iter() {
+        let project = pending.cell(row, "project");
+        if !project.is_empty() {
+            *counts.entry(project.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    let range = s.user_data::<AppState>().expect("App state").config.projects_range.clone();
+
+    // The hook tags every interval with the project of its task
+    let mut text = String::new();
+    let intervals = taskwarrior::get_interval_list_for(&mut text, &range)?;
+    let mut seconds: BTreeMap<String, u64> = BTreeMap::new();
+    for interval in timesheet::intervals_from_list(&intervals) {
+        if let Some(project) = interval.tags.split(", ").find(|tag| projects.iter().any(|p| p == tag)) {
+            *seconds.entry(project.to_string()).or_insert(0) += interval.seconds;
+        }
+    }
+
+    // "All" also counts the tasks without a project
+    let mut root = build(&projects, &counts, &seconds);
+    root.count = pending.rows.len();
+
+    let state = s.user_data::<AppState>().expect("App state");
+    state.project_tree = root;
+    state.loaded.insert("projects", key);
+    show(s);
+
+    Ok(())
+}
+
+/// Lists the loaded projects, keeping the selection.
+fn show(s: &mut Cursive) {
+    let state = s.user_data::<AppState>().expect("App state");
+    let items = lines(&state.project_tree, &state.collapsed_projects);
+
+    s.call_on_name("projects_tree", |view: &mut SelectView<String>| {
+        let selected = view.selection();
+        view.clear();
+        view.add_all(items);
+        let index = selected
+            .and_then(|selected| view.iter().position(|(_, value)| *value == *selected))
+            .unwrap_or_default();
+        view.set_selection(index);
+    });
+}
+
+/// Shows only the tasks of the chosen project and its subprojects.
+fn select(s: &mut Cursive, project: &str) {
+    s.user_data::<AppState>().expect("App state").project = project.to_string();
+}
+
+fn set_collapsed(s: &mut Cursive, collapsed: bool) {
+    let project = s.call_on_name("projects_tree", |view: &mut SelectView<String>| view.selection())
+        .flatten();
+    let project = match project {
+        Some(project) if !project.is_empty() => project.to_string(),
+        _ => return
+    };
+
+    let state = s.user_data::<AppState>().expect("App state");
+    if collapsed {
+        state.collapsed_projects.insert(project);
+    } else {
+        state.collapsed_projects.remove(&project);
+    }
+    show(s);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Node {
+        let projects: Vec<String> = vec!["home".to_string(), "work.api".to_string(), "work.web".to_string()];
+        let counts: BTreeMap<String, usize> = vec![("home".to_string(), 1), ("work.api".to_string(), 2), ("work.web".to_string(), 3)]
+            .into_iter()
+            .collect();
+        let seconds: BTreeMap<String, u64> = vec![("work.api".to_string(), 60), ("work.web".to_string(), 120)]
+            .into_iter()
+            .collect();
+
+        build(&projects, &counts, &seconds)
+    }
+
+    #[test]
+    fn hierarchy() {
+        let root = tree();
+        let work = &root.children["work"];
+
+        assert_eq!(6, root.count);
+        assert_eq!((5, 180), (work.count, work.seconds));
+        assert_eq!("work.web", work.children["web"].path);
+    }
+
+    #[test]
+    fn collapsed_lines() {
+        let mut collapsed = BTreeSet::new();
+        collapsed.insert("work".to_string());
+
+        assert_eq!(vec![
+            ("All (6)".to_string(), "".to_string()),
+            ("  home (1) 0:00:00".to_string(), "home".to_string()),
+            ("▸ work (5) 0:03:00".to_string(), "work".to_string())
+        ], lines(&tree(), &collapsed));
+        assert_eq!(5, lines(&tree(), &BTreeSet::new()).len());
+    }
+}
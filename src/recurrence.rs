@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use cursive::Cursive;
+use cursive::views::{Dialog, OnEventView};
+
+use crate::taskwarrior;
+use crate::undo::{self, Inverse};
+
+/// How a recurring instance relates to its template (the parent task).
+#[derive(PartialEq, Debug)]
+pub struct Recurrence {
+    pub period: String,
+    pub until: String,
+    pub parent: String,
+    pub parent_description: String,
+    /// Pending instances of the series, this one included.
+    pub instances: usize
+}
+
+impl Recurrence {
+    /// The recurrence of `task_id`, or `None` for ordinary tasks.
+    pub fn of<'a>(task_id: &str) -> Result<Option<Recurrence>, &'a str> {
+        let parent = taskwarrior::get_task_attribute(task_id, "parent")?;
+        if parent.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Recurrence {
+            period: taskwarrior::get_task_attribute(task_id, "recur")?,
+            until: taskwarrior::get_task_attribute(task_id, "until")?,
+            parent_description: taskwarrior::get_task_attribute(&parent, "description")?,
+            instances: taskwarrior::get_uuids(&format!("status:pending parent:{}", parent))?.len(),
+            parent
+        }))
+    }
+
+    pub fn describe(&self) -> String {
+        let until = match self.until.get(..10) {
+            Some(date) => format!(" until {}", date),
+            None => String::new()
+        };
+
+        format!("Recurs {}{}, {} pending\nTemplate {} \"{}\"",
+            self.period,
+            until,
+            self.instances,
+            self.parent.get(..8).unwrap_or(&self.parent),
+            self.parent_description)
+    }
+
+    /// Undo steps for changing this instance, or the template and every
+    /// pending instance.
+    pub fn undo_steps(&self, series: bool, timew: usize) -> Vec<Inverse> {
+        let others = if series { self.instances } else { 0 };
+        let mut inverse = vec![Inverse::TaskUndo; others];
+        inverse.extend(undo::task_change(timew));
+
+        inverse
+    }
+}
+
+/// Attributes for `task add`. Taskwarrior refuses a recurrence without
+/// a due date.
+pub fn add_args<'a>(due: &str, recur: &str, until: &str) -> Result<Vec<String>, &'a str> {
+    if !recur.is_empty() && due.is_empty() {
+        return Err("A recurring task needs a due date");
+    }
+    if !until.is_empty() && recur.is_empty() {
+        return Err("Until only applies to recurring tasks");
+    }
+
+    Ok([("due", due), ("recur", recur), ("until", until)]
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| format!("{}:{}", name, value))
+        .collect())
+}
+
+/// Asks whether `verb` applies to this instance only or to the whole
+/// series, then calls `action` with `true` for the series.
+pub fn choose<F>(s: &mut Cursive, verb: &str, recurrence: &Recurrence, action: F)
+where
+    F: Fn(&mut Cursive, bool) + 'static
+{
+    let action = Rc::new(action);
+    let series_action = action.clone();
+
+    s.add_layer(OnEventView::new(
+        Dialog::text(format!("{} this instance only, or all {} pending instances of \"{}\"?",
+                verb, recurrence.instances, recurrence.parent_description))
+            .title("Recurring task")
+            .button("This instance", move |s| {
+                s.pop_layer();
+                action(s, false);
+            })
+            .button("All instances", move |s| {
+                s.pop_layer();
+                series_action(s, true);
+            })
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weekly() -> Recurrence {
+        Recurrence {
+            period: "weekly".to_string(),
+            until: "2026-12-01T00:00:00".to_string(),
+            parent: "1a2b3c4d-0000-0000-0000-000000000000".to_string(),
+            parent_description: "Water plants".to_string(),
+            instances: 2
+        }
+    }
+
+    #[test]
+    fn describe() {
+        assert_eq!("Recurs weekly until 2026-12-01, 2 pending\nTemplate 1a2b3c4d \"Water plants\"", weekly().describe());
+    }
+
+    #[test]
+    fn undo_steps() {
+        assert_eq!(vec![Inverse::TaskUndo], weekly().undo_steps(false, 0));
+        assert_eq!(vec![Inverse::TaskUndo, Inverse::TaskUndo, Inverse::TaskUndo, Inverse::TimewUndo],
+            weekly().undo_steps(true, 1));
+    }
+
+    #[test]
+    fn add_arguments() {
+        assert_eq!(Vec::<String>::new(), add_args("", "", "").unwrap());
+        assert_eq!(vec!["due:eom", "recur:monthly", "until:2027-01-01"], add_args("eom", "monthly", "2027-01-01").unwrap());
+        assert!(add_args("", "weekly", "").is_err());
+        assert!(add_args("tomorrow", "", "eoy").is_err());
+    }
+}
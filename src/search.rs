@@ -0,0 +1,45 @@
+use cursive::Cursive;
+use cursive::event::Key;
+use cursive::traits::*;
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, TextView};
+
+use crate::cursive_simple_table_view::SimpleTableView;
+
+/// Searches `table` while the pattern is typed. Enter keeps the match
+/// highlighted for `n` and `N`; Esc clears it and goes back to the row
+/// the search started from.
+pub fn show(s: &mut Cursive, table: &'static str) {
+    let origin = s.call_on_name(table, |view: &mut SimpleTableView| view.focus_row()).flatten();
+
+    s.add_layer(OnEventView::new(
+        Dialog::around(LinearLayout::horizontal()
+                .child(TextView::new("/"))
+                .child(EditView::new()
+                    .filler(" ")
+                    .on_edit(move |s, text, _| {
+                        let found = s.call_on_name(table, |view: &mut SimpleTableView| view.set_search(text))
+                            .unwrap_or_default();
+                        let status = if found || text.is_empty() { "" } else { " no match" };
+                        s.call_on_name("search_status", |view: &mut TextView| view.set_content(status));
+                    })
+                    .on_submit(|s, _| {
+                        s.pop_layer();
+                    })
+                    .fixed_width(30))
+                .child(TextView::new("").with_name("search_status").fixed_width(9)))
+            .title("Search"))
+        .on_event(Key::Esc, move |s| {
+            s.pop_layer();
+            s.call_on_name(table, |view: &mut SimpleTableView| {
+                view.set_search("");
+                if let Some(row) = origin {
+                    view.set_focus_row(row);
+                }
+            });
+        }));
+}
+
+/// Moves to the next match of the last search, or the previous one.
+pub fn next(s: &mut Cursive, table: &str, forward: bool) {
+    s.call_on_name(table, |view: &mut SimpleTableView| view.search_next(forward));
+}
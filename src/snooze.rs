@@ -0,0 +1,100 @@
+use cursive::Cursive;
+use cursive::views::{Dialog, OnEventView, SelectView};
+
+use crate::date_input;
+use crate::state::AppState;
+use crate::taskwarrior;
+use crate::undo::{self, Inverse};
+
+/// A date attribute and the value to give it; `None` asks for a date.
+type Choice = (&'static str, Option<&'static str>);
+
+/// Offers to snooze the task (`wait:`) or set its `scheduled:` and
+/// `until:` dates.
+pub fn show(s: &mut Cursive, task_id: &str) {
+    let uuid = match taskwarrior::get_task_attribute(task_id, "uuid") {
+        Ok(uuid) => uuid,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let choices = SelectView::<Choice>::new()
+        .item("Snooze until tomorrow", ("wait", Some("tomorrow")))
+        .item("Snooze for a week", ("wait", Some("1w")))
+        .item("Snooze until…", ("wait", None))
+        .item("Wake up now", ("wait", Some("")))
+        .item("Scheduled…", ("scheduled", None))
+        .item("Until…", ("until", None))
+        .on_submit(move |s: &mut Cursive, (attribute, value): &Choice| {
+            s.pop_layer();
+            match value {
+                Some(value) => set_date(s, &uuid, attribute, value),
+                None => {
+                    let uuid = uuid.clone();
+                    let attribute = *attribute;
+                    prompt_date(s, attribute, move |s, value| set_date(s, &uuid, attribute, value));
+                }
+            }
+        });
+
+    s.add_layer(OnEventView::new(
+        Dialog::around(choices)
+            .title("Dates")
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+/// Asks for a Taskwarrior date such as `friday`, `eow` or `2026-11-01`.
+pub fn prompt_date<F>(s: &mut Cursive, title: &str, on_submit: F)
+where
+    F: Fn(&mut Cursive, &str) + 'static
+{
+    s.add_layer(OnEventView::new(
+        Dialog::around(date_input::view("date_prompt", 20, move |s| {
+                match date_input::content(s, "date_prompt") {
+                    Ok(date) => {
+                        s.pop_layer();
+                        on_submit(s, &date);
+                    },
+                    Err(e) => s.add_layer(Dialog::info(e))
+                }
+            }))
+            .title(title)
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+/// Shows the hidden tasks with their wake-up dates, or goes back to the
+/// usual report.
+pub fn toggle_waiting(s: &mut Cursive) {
+    let state = s.user_data::<AppState>().expect("App state");
+    state.report = if state.report == "waiting" {
+        "next".to_string()
+    } else {
+        "waiting".to_string()
+    };
+}
+
+fn set_date(s: &mut Cursive, uuid: &str, attribute: &str, value: &str) {
+    let previous = match taskwarrior::get_task_attribute(uuid, attribute) {
+        Ok(previous) => previous,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    if let Err(e) = taskwarrior::modify_task(uuid, &[&format!("{}:{}", attribute, value)]) {
+        return s.add_layer(Dialog::info(e));
+    }
+
+    let description = taskwarrior::get_task_attribute(uuid, "description").unwrap_or_default();
+    let message = if value.is_empty() {
+        format!("Cleared {} of \"{}\"", attribute, description)
+    } else {
+        format!("Set {} of \"{}\" to {}", attribute, description, value)
+    };
+
+    undo::record(s, message.clone(), vec![Inverse::Modify(uuid.to_string(), vec![format!("{}:{}", attribute, previous)])]);
+    s.user_data::<AppState>().expect("App state").notify(message);
+}
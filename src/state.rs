@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Duration as Days, Local, NaiveDate};
+
+use crate::config::Config;
+use crate::idle::Activity;
+use crate::layout::Layout;
+use crate::pomodoro::Pomodoro;
+use crate::projects::Node;
+use crate::taskwarrior::ReportLayout;
+use crate::undo::UndoStack;
+
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything the UI callbacks share, stored as the Cursive user data.
+pub struct AppState {
+    pub config: Config,
+    pub report: String,
+    /// Settings of the reports shown so far, read once each.
+    pub report_layouts: BTreeMap<String, ReportLayout>,
+    pub project: String,
+    /// Tags the tasks must (`true`) or must not (`false`) carry.
+    pub tags: BTreeMap<String, bool>,
+    pub interval_tag: Option<String>,
+    /// Day chosen in the calendar, narrowing down both panes.
+    pub day: Option<NaiveDate>,
+    /// UUIDs of the tasks marked for bulk changes.
+    pub marked: BTreeSet<String>,
+    pub last_refresh: Option<DateTime<Local>>,
+    pub pomodoro: Option<Pomodoro>,
+    pub pomodoros_completed: u32,
+    pub last_activity: Activity,
+    pub idle_prompted: Option<DateTime<Local>>,
+    pub undo: UndoStack,
+    pub collapsed_projects: BTreeSet<String>,
+    /// The projects as last loaded, redrawn when a project collapses.
+    pub project_tree: Node,
+    pub layout: Layout,
+    /// Where `taskwarrior::data_modified` looks for changes.
+    pub data_dirs: Vec<PathBuf>,
+    /// What the sidebar last loaded, by source, so unchanged data is not
+    /// read again.
+    pub loaded: BTreeMap<&'static str, String>,
+    message: Option<(String, Instant)>,
+    errors: BTreeMap<&'static str, String>
+}
+
+impl AppState {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            report: "next".to_string(),
+            report_layouts: BTreeMap::new(),
+            project: String::new(),
+            tags: BTreeMap::new(),
+            interval_tag: None,
+            day: None,
+            marked: BTreeSet::new(),
+            last_refresh: None,
+            pomodoro: None,
+            pomodoros_completed: 0,
+            last_activity: Rc::new(Cell::new(Local::now())),
+            idle_prompted: None,
+            undo: UndoStack::default(),
+            collapsed_projects: BTreeSet::new(),
+            project_tree: Node::default(),
+            layout: Layout::default(),
+            data_dirs: Vec::new(),
+            loaded: BTreeMap::new(),
+            message: None,
+            errors: BTreeMap::new()
+        }
+    }
+
+    /// Remembers the outcome of refreshing `source`; an error stays until
+    /// that same source refreshes successfully.
+    pub fn record_refresh(&mut self, source: &'static str, result: Result<(), &str>) {
+        match result {
+            Ok(()) => {
+                self.last_refresh = Some(Local::now());
+                self.errors.remove(source);
+            },
+            Err(e) => {
+                self.errors.insert(source, e.to_string());
+            }
+        }
+    }
+
+    /// Taskwarrior filter for the chosen project and tags.
+    pub fn filter(&self) -> String {
+        let project = Some(&self.project)
+            .filter(|project| !project.is_empty())
+            .map(|project| format!("project:{}", project));
+        let tags = self.tags
+            .iter()
+            .map(|(tag, include)| format!("{}{}", if *include { "+" } else { "-" }, tag));
+        let day = self.day.map(|day| format!("due:{}", day));
+
+        project.into_iter().chain(tags).chain(day).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Timewarrior range and tag for the intervals pane.
+    pub fn interval_filter(&self) -> String {
+        let range = self.day.map(|day| format!("{} - {}", day, day + Days::days(1)));
+
+        range.into_iter().chain(self.interval_tag.clone()).collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.errors.values().next().map(|e| e.as_str())
+    }
+
+    /// Shows a short-lived message on the status line.
+    pub fn notify<S: Into<String>>(&mut self, message: S) {
+        self.message = Some((message.into(), Instant::now()));
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        match &self.message {
+            Some((message, at)) if at.elapsed() < MESSAGE_TIMEOUT => Some(message.as_str()),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter() {
+        let mut state = AppState::new(Config::default());
+        assert_eq!("", state.filter());
+
+        state.project = "work.api".to_string();
+        state.tags.insert("urgent".to_string(), true);
+        state.tags.insert("blocked".to_string(), false);
+        assert_eq!("project:work.api -blocked +urgent", state.filter());
+
+        state.day = Some(NaiveDate::from_ymd(2026, 10, 31));
+        state.interval_tag = Some("work".to_string());
+        assert_eq!("project:work.api -blocked +urgent due:2026-10-31", state.filter());
+        assert_eq!("2026-10-31 - 2026-11-01 work", state.interval_filter());
+    }
+}
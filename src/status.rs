@@ -0,0 +1,169 @@
+use chrono::{DateTime, Local};
+
+use crate::taskwarrior::{self, TaskList};
+use crate::timesheet::{self, json_string};
+
+pub const DEFAULT_TEMPLATE: &str = "{description} {elapsed} | today {today}";
+pub const DEFAULT_IDLE_TEMPLATE: &str = "idle | today {today}";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StatusFormat {
+    Plain,
+    Json,
+    Tmux
+}
+
+impl StatusFormat {
+    pub fn parse(name: &str) -> Option<StatusFormat> {
+        match name.to_lowercase().as_str() {
+            "plain" => Some(StatusFormat::Plain),
+            "json" | "waybar" => Some(StatusFormat::Json),
+            "tmux" => Some(StatusFormat::Tmux),
+            _ => None
+        }
+    }
+}
+
+/// Snapshot of what is being tracked right now.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Status {
+    pub id: String,
+    pub description: String,
+    pub start: Option<DateTime<Local>>,
+    pub elapsed: Option<u64>,
+    pub today: u64,
+    pub week: u64
+}
+
+impl Status {
+    pub fn is_active(&self) -> bool {
+        self.elapsed.is_some()
+    }
+}
+
+pub fn current<'a>() -> Result<Status, &'a str> {
+    let mut text = String::new();
+    let active = taskwarrior::get_active_task_list(&mut text)?;
+    let start = taskwarrior::get_active_start()?;
+
+    let mut status = Status {
+        today: tracked(":day")?,
+        week: tracked(":week")?,
+        ..Status::default()
+    };
+    // With several active tasks, only one has its time tracked
+    let tags = if active.rows.len() > 1 {
+        taskwarrior::get_active_tags()?
+    } else {
+        Vec::new()
+    };
+    if let Some(row) = tracked_row(&active, &tags) {
+        status.id = active.cell(row, "ID").to_string();
+        status.description = active.cell(row, "Description").to_string();
+    }
+    if let Some(start) = start {
+        status.start = Some(start);
+        status.elapsed = Some((Local::now() - start).num_seconds().max(0) as u64);
+    }
+
+    Ok(status)
+}
+
+/// The active task whose description and project are among the `tags` of
+/// the tracked interval, or the first one.
+fn tracked_row<'l, 'a>(active: &'l TaskList<'a>, tags: &[String]) -> Option<&'l Vec<&'a str>> {
+    let tracked = |row: &&Vec<&str>| {
+        let project = active.cell(row, "Project");
+        tags.iter().any(|tag| tag == active.cell(row, "Description"))
+            && (project.is_empty() || tags.iter().any(|tag| tag == project))
+    };
+
+    active.rows.iter().find(tracked).or_else(|| active.rows.first())
+}
+
+/// Total time tracked within a Timewarrior range.
+pub fn tracked<'a>(range: &str) -> Result<u64, &'a str> {
+    let mut text = String::new();
+    let intervals = taskwarrior::get_interval_list_for(&mut text, range)?;
+
+    Ok(timesheet::intervals_from_list(&intervals)
+        .iter()
+        .map(|i| i.seconds)
+        .sum())
+}
+
+/// Fills in `{id}`, `{description}`, `{elapsed}`, `{today}` and `{week}`.
+pub fn expand(template: &str, status: &Status) -> String {
+    template
+        .replace("{id}", &status.id)
+        .replace("{description}", &status.description)
+        .replace("{elapsed}", &timesheet::format_duration(status.elapsed.unwrap_or_default()))
+        .replace("{today}", &timesheet::format_duration(status.today))
+        .replace("{week}", &timesheet::format_duration(status.week))
+        .trim()
+        .to_string()
+}
+
+pub fn render(status: &Status, format: StatusFormat, template: &str, idle_template: &str) -> String {
+    let text = if status.is_active() {
+        expand(template, status)
+    } else {
+        expand(idle_template, status)
+    };
+
+    match format {
+        StatusFormat::Plain => text,
+        StatusFormat::Json => {
+            let class = if status.is_active() { "active" } else { "idle" };
+            let tooltip = format!("Tracked today: {}", timesheet::format_duration(status.today));
+            format!("{{\"text\":{},\"tooltip\":{},\"class\":{}}}",
+                json_string(&text), json_string(&tooltip), json_string(class))
+        },
+        StatusFormat::Tmux => {
+            let colour = if status.is_active() { "green" } else { "colour244" };
+            format!("#[fg={}]{}#[default]", colour, text.replace('#', "##"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn active() -> Status {
+        Status {
+            id: "3".to_string(),
+            description: "Write \"report\"".to_string(),
+            start: None,
+            elapsed: Some(754),
+            today: 7200,
+            week: 36000
+        }
+    }
+
+    #[test]
+    fn tracked_row_matches_interval_tags() {
+        let active = TaskList {
+            colsizes: vec![2, 4, 6],
+            columns: vec!["ID", "Project", "Description"],
+            rows: vec![vec!["1", "home", "Cook"], vec!["4", "work", "Review"], vec!["7", "", "Read"]]
+        };
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(Some(&active.rows[1]), tracked_row(&active, &tags(&["Review", "work", "meeting"])));
+        assert_eq!(Some(&active.rows[2]), tracked_row(&active, &tags(&["Read"])));
+        // The description alone is not enough when the project differs
+        assert_eq!(Some(&active.rows[0]), tracked_row(&active, &tags(&["Review", "home"])));
+        assert_eq!(Some(&active.rows[0]), tracked_row(&active, &[]));
+    }
+
+    #[test]
+    fn render_formats() {
+        assert_eq!("Write \"report\" 0:12:34 | today 2:00:00",
+            render(&active(), StatusFormat::Plain, DEFAULT_TEMPLATE, DEFAULT_IDLE_TEMPLATE));
+        assert_eq!("{\"text\":\"3 Write \\\"report\\\"\",\"tooltip\":\"Tracked today: 2:00:00\",\"class\":\"active\"}",
+            render(&active(), StatusFormat::Json, "{id} {description}", DEFAULT_IDLE_TEMPLATE));
+        assert_eq!("#[fg=colour244]idle | today 0:00:00#[default]",
+            render(&Status::default(), StatusFormat::Tmux, DEFAULT_TEMPLATE, DEFAULT_IDLE_TEMPLATE));
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Instant;
+
+use cursive::Cursive;
+use cursive::theme::{BaseColor, Color, ColorStyle, Effect, Style};
+use cursive::traits::*;
+use cursive::utils::markup::StyledString;
+use cursive::views::TextView;
+
+use crate::state::AppState;
+use crate::status::Status;
+use crate::timesheet::format_duration;
+
+pub fn view() -> impl View {
+    TextView::new("")
+        .no_wrap()
+        .with_name("status_bar")
+}
+
+/// Redraws the status line from the latest tracking snapshot and the
+/// refresh state kept in `AppState`.
+pub fn update(s: &mut Cursive, status: Result<Status, &str>) {
+    let content = {
+        let state = s.user_data::<AppState>().expect("App state");
+        render(status, state)
+    };
+
+    s.call_on_name("status_bar", |view: &mut TextView| {
+        view.set_content(content);
+    });
+}
+
+fn render(status: Result<Status, &str>, state: &AppState) -> StyledString {
+    let dim = Style::from(ColorStyle::tertiary());
+    let separator = || StyledString::styled(" │ ", dim);
+    let mut line = StyledString::new();
+
+    match &status {
+        Ok(status) if status.is_active() => {
+            line.append_styled(
+                format!("▶ {} {} ", status.id, status.description),
+                Style::from(ColorStyle::secondary()).combine(Effect::Bold));
+            line.append_styled(
+                format_duration(status.elapsed.unwrap_or_default()),
+                ColorStyle::secondary());
+        },
+        Ok(_) => line.append_styled("■ idle", dim),
+        Err(_) => line.append_styled("? unknown", dim)
+    }
+
+    if let Some(pomodoro) = &state.pomodoro {
+        line.append(separator());
+        line.append_styled(pomodoro.label(Instant::now()),
+            Style::from(Color::Light(BaseColor::Red)).combine(Effect::Bold));
+    }
+
+    if let Ok(status) = &status {
+        line.append(separator());
+        line.append_plain(format!("today {}", format_duration(status.today)));
+        line.append(separator());
+        line.append_plain(format!("week {}", format_duration(status.week)));
+    }
+
+    line.append(separator());
+    line.append_plain(format!("report {}", state.report));
+    let filter = state.filter();
+    if !filter.is_empty() {
+        line.append_plain(format!(" [{}]", filter));
+    }
+    let interval_filter = state.interval_filter();
+    if !interval_filter.is_empty() {
+        line.append_plain(format!(" intervals [{}]", interval_filter));
+    }
+
+    line.append(separator());
+    let error = state.last_error().or_else(|| status.err());
+    match (state.message(), error, state.last_refresh) {
+        (Some(message), _, _) => line.append_styled(
+            message,
+            Style::from(ColorStyle::title_secondary()).combine(Effect::Bold)),
+        (None, Some(e), _) => line.append_styled(
+            format!("error: {}", e),
+            Color::Light(BaseColor::Red)),
+        (None, None, Some(time)) => line.append_styled(
+            format!("refreshed {}", time.format("%H:%M:%S")), dim),
+        (None, None, None) => line.append_styled("loading…", dim)
+    }
+
+    line
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use cursive::Cursive;
+use cursive::traits::*;
+use cursive::views::{Dialog, OnEventView, SelectView};
+
+use crate::state::AppState;
+use crate::taskwarrior;
+use crate::undo::{self, Inverse};
+
+/// A tag and the number of pending tasks carrying it.
+type TagCount = (String, usize);
+
+/// Tags `task _tags` always lists, as Taskwarrior gives them a meaning.
+const PSEUDO_TAGS: [&str; 4] = ["next", "nocal", "nocolor", "nonag"];
+
+/// Next filter state of a tag: not filtered, then included, then excluded.
+pub fn cycle(current: Option<bool>) -> Option<bool> {
+    match current {
+        None => Some(true),
+        Some(true) => Some(false),
+        Some(false) => None
+    }
+}
+
+pub fn label(tag: &str, count: usize, include: Option<bool>) -> String {
+    let sign = match include {
+        Some(true) => "+",
+        Some(false) => "-",
+        None => " "
+    };
+
+    format!("{} {} ({})", sign, tag, count)
+}
+
+/// Every tag Taskwarrior knows of, with its pending task count.
+fn tag_counts<'a>() -> Result<Vec<TagCount>, &'a str> {
+    let mut text = String::new();
+    let pending = taskwarrior::get_custom_report(&mut text, "status:pending", &["uuid", "tags"])?;
+    let carried: Vec<&str> = pending.rows
+        .iter()
+        .flat_map(|row| pending.cell(row, "tags").split_whitespace())
+        .collect();
+
+    Ok(count_tags(taskwarrior::get_tags()?, &carried))
+}
+
+/// Counts the `carried` tags. The pseudo-tags among the `known` ones are
+/// left out unless a task carries them.
+fn count_tags(known: Vec<String>, carried: &[&str]) -> Vec<TagCount> {
+    let mut counts: BTreeMap<String, usize> = known
+        .into_iter()
+        .filter(|tag| !PSEUDO_TAGS.contains(&tag.as_str()))
+        .map(|tag| (tag, 0))
+        .collect();
+    for tag in carried {
+        *counts.entry(tag.to_string()).or_insert(0) += 1;
+    }
+
+    counts.into_iter().collect()
+}
+
+/// Lists the tags to filter the tasks pane with (`+`, `-`, Enter cycles)
+/// and to add to or remove from the marked tasks (`a`, `r`).
+pub fn show_browser(s: &mut Cursive) {
+    let counts = match tag_counts() {
+        Ok(counts) => counts,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let mut list = SelectView::<TagCount>::new()
+        .on_submit(|s: &mut Cursive, (tag, _): &TagCount| {
+            let state = s.user_data::<AppState>().expect("App state");
+            match cycle(state.tags.get(tag).copied()) {
+                Some(include) => state.tags.insert(tag.clone(), include),
+                None => state.tags.remove(tag)
+            };
+            relabel(s);
+        });
+    list.add_all(counts.into_iter().map(|count| (String::new(), count)));
+
+    s.add_layer(OnEventView::new(
+        Dialog::around(list.with_name("tag_list").scrollable().fixed_height(15))
+            .title("Tags")
+            .button("Add to marked", |s| tag_marked(s, true))
+            .button("Remove from marked", |s| tag_marked(s, false))
+            .dismiss_button("Close"))
+        .on_event('+', |s| set_filter(s, true))
+        .on_event('-', |s| set_filter(s, false))
+        .on_event('a', |s| tag_marked(s, true))
+        .on_event('r', |s| tag_marked(s, false))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+    relabel(s);
+}
+
+/// Lists the same tags to narrow down the intervals pane.
+pub fn show_interval_filter(s: &mut Cursive) {
+    let counts = match tag_counts() {
+        Ok(counts) => counts,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let mut list = SelectView::<String>::new()
+        .item("All intervals", String::new())
+        .on_submit(|s: &mut Cursive, tag: &String| {
+            let state = s.user_data::<AppState>().expect("App state");
+            state.interval_tag = Some(tag.clone()).filter(|tag| !tag.is_empty());
+            s.pop_layer();
+        });
+    list.add_all(counts.into_iter().map(|(tag, count)| (label(&tag, count, None), tag)));
+
+    s.add_layer(OnEventView::new(
+        Dialog::around(list.scrollable().fixed_height(15))
+            .title("Intervals tagged")
+            .dismiss_button("Cancel"))
+        .on_event(cursive::event::Key::Esc, |s| {
+            s.pop_layer();
+        }));
+}
+
+fn set_filter(s: &mut Cursive, include: bool) {
+    if let Some((tag, _)) = selected(s) {
+        s.user_data::<AppState>().expect("App state").tags.insert(tag, include);
+        relabel(s);
+    }
+}
+
+/// Adds the focused tag to every marked task, or removes it.
+fn tag_marked(s: &mut Cursive, add: bool) {
+    let tag = match selected(s) {
+        Some((tag, _)) => tag,
+        None => return
+    };
+
+    let marked: Vec<String> = s.user_data::<AppState>().expect("App state").marked
+        .iter()
+        .cloned()
+        .collect();
+    if marked.is_empty() {
+        return s.add_layer(Dialog::info("Mark tasks with `m` first"));
+    }
+
+    let (change, inverse) = if add {
+        (format!("+{}", tag), format!("-{}", tag))
+    } else {
+        (format!("-{}", tag), format!("+{}", tag))
+    };
+
+    let mut inverses = Vec::new();
+    for uuid in marked.iter() {
+        if let Err(e) = taskwarrior::modify_task(uuid, &[&change]) {
+            s.add_layer(Dialog::info(e));
+            break;
+        }
+        inverses.push(Inverse::Modify(uuid.clone(), vec![inverse.clone()]));
+    }
+
+    let description = format!("{} on {} tasks", change, inverses.len());
+    undo::record(s, description.clone(), inverses);
+    s.user_data::<AppState>().expect("App state").notify(description);
+}
+
+fn selected(s: &mut Cursive) -> Option<TagCount> {
+    s.call_on_name("tag_list", |view: &mut SelectView<TagCount>| view.selection())
+        .flatten()
+        .map(|tag| (*tag).clone())
+}
+
+/// Rewrites the labels after the tag filters changed.
+fn relabel(s: &mut Cursive) {
+    let tags = s.user_data::<AppState>().expect("App state").tags.clone();
+
+    s.call_on_name("tag_list", |view: &mut SelectView<TagCount>| {
+        let focus = view.selected_id().unwrap_or_default();
+        let items: Vec<TagCount> = view.iter().map(|(_, count)| count.clone()).collect();
+        view.clear();
+        view.add_all(items.into_iter().map(|(tag, count)| {
+            (label(&tag, count, tags.get(&tag).copied()), (tag, count))
+        }));
+        view.set_selection(focus);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_filters() {
+        assert_eq!(Some(true), cycle(None));
+        assert_eq!(Some(false), cycle(Some(true)));
+        assert_eq!(None, cycle(Some(false)));
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!("+ work (3)", label("work", 3, Some(true)));
+        assert_eq!("- home (0)", label("home", 0, Some(false)));
+        assert_eq!("  errand (1)", label("errand", 1, None));
+    }
+
+    #[test]
+    fn pseudo_tags_are_left_out() {
+        let known = vec!["home".to_string(), "next".to_string(), "nocal".to_string(), "work".to_string()];
+
+        assert_eq!(vec![("home".to_string(), 0), ("work".to_string(), 2)], count_tags(known.clone(), &["work", "work"]));
+        assert_eq!(vec![("home".to_string(), 0), ("next".to_string(), 1), ("work".to_string(), 0)], count_tags(known, &["next"]));
+    }
+}
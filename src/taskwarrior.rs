@@ -1,7 +1,42 @@
 use std::error::Error;
 use std::str;
 use std::cmp;
-use std::process::Command;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::io;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Set when the on-modify hook is missing, so starting and stopping tasks
+/// has to drive Timewarrior from here.
+static TIMEW_FALLBACK: AtomicBool = AtomicBool::new(false);
+
+pub fn set_timew_fallback(enabled: bool) {
+    TIMEW_FALLBACK.store(enabled, Ordering::Relaxed);
+}
+
+fn timew_fallback() -> bool {
+    TIMEW_FALLBACK.load(Ordering::Relaxed)
+}
+
+/// Set when the on-modify hook is installed and tracks started tasks.
+static TIMEW_HOOK: AtomicBool = AtomicBool::new(false);
+
+pub fn set_timew_hook(installed: bool) {
+    TIMEW_HOOK.store(installed, Ordering::Relaxed);
+}
+
+/// Whether starting and stopping tasks reaches Timewarrior at all, through
+/// the hook or from here.
+fn timew_driven() -> bool {
+    timew_fallback() || TIMEW_HOOK.load(Ordering::Relaxed)
+}
 
 pub struct TaskList<'a> {
     pub colsizes: Vec<usize>,
@@ -9,7 +44,21 @@ pub struct TaskList<'a> {
     pub rows: Vec<Vec<&'a str>>
 }
 
-pub fn get_active_tasks<'a>() -> Result<Vec<usize>, Box<dyn Error>> {
+impl<'a> TaskList<'a> {
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| *c == name)
+    }
+
+    pub fn cell(&self, row: &[&'a str], name: &str) -> &'a str {
+        self.column_index(name)
+            .and_then(|i| row.get(i))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// IDs of the active tasks.
+pub fn get_active_tasks<'a>() -> Result<Vec<String>, Box<dyn Error>> {
     let stdout = Command::new("task")
         .arg("active")
         .output()?
@@ -17,23 +66,174 @@ pub fn get_active_tasks<'a>() -> Result<Vec<usize>, Box<dyn Error>> {
 
     let text = String::from_utf8(stdout)?;
 
-    let mut list = parse_task_list(&text, false)?;
+    let list = parse_task_list(&text, false)?;
 
-    let indices: Vec<usize> = list.rows
-        .iter_mut()
-        .map(|row| {
-            let index = *row.get(0).unwrap();
-            index.parse::<usize>().unwrap() - 1
-        })
+    let ids: Vec<String> = list.rows
+        .iter()
+        .map(|row| list.cell(row, "ID").to_string())
         .collect();
 
-    Ok(indices)
+    Ok(ids)
+}
+
+/// UUIDs of the active tasks, which stay stable while IDs get renumbered.
+pub fn get_active_uuids<'a>() -> Result<Vec<String>, &'a str> {
+    get_values(&["+ACTIVE", "_uuids"])
+}
+
+/// UUIDs of the tasks matching `filter`.
+pub fn get_uuids<'a>(filter: &str) -> Result<Vec<String>, &'a str> {
+    let mut args: Vec<&str> = filter.split_whitespace().collect();
+    args.push("_uuids");
+
+    get_values(&args)
+}
+
+/// Directories whose files change with the Taskwarrior and Timewarrior
+/// data, looked up once so changes can be noticed without running either.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    let mut dirs = Vec::new();
+
+    let output = Command::new("task")
+        .args(["rc.verbose=nothing", "_get", "rc.data.location"])
+        .output();
+    if let Ok(o) = output {
+        let location = String::from_utf8(o.stdout).unwrap_or_default();
+        match location.trim() {
+            "" => (),
+            location if location.starts_with("~/") => dirs.push(home.join(&location[2..])),
+            location => dirs.push(PathBuf::from(location))
+        }
+    }
+
+    match env::var_os("TIMEWARRIORDB") {
+        Some(dir) => dirs.push(PathBuf::from(dir).join("data")),
+        None => {
+            dirs.push(home.join(".timewarrior").join("data"));
+            let data_home = env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".local").join("share"));
+            dirs.push(data_home.join("timewarrior").join("data"));
+        }
+    }
+
+    dirs
+}
+
+/// When a file in `dirs` last changed.
+pub fn data_modified(dirs: &[PathBuf]) -> Option<SystemTime> {
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// Projects of the pending tasks, as dotted names like `work.api`.
+pub fn get_projects<'a>() -> Result<Vec<String>, &'a str> {
+    get_values(&["_projects"])
+}
+
+/// Tags in use, including the ones of completed tasks.
+pub fn get_tags<'a>() -> Result<Vec<String>, &'a str> {
+    get_values(&["_tags"])
+}
+
+/// Runs a helper command such as `_uuids` that prints one value per line.
+fn get_values<'a>(args: &[&str]) -> Result<Vec<String>, &'a str> {
+    let output = Command::new("task")
+        .args(args)
+        .output()
+        .map_err(|_| "Could not run task")?;
+
+    Ok(String::from_utf8(output.stdout)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// Runs a throwaway report showing `columns` for the tasks matching
+/// `filter`. The labels are the column names, so cells can be looked up
+/// with `TaskList::cell(row, "description")`.
+pub fn get_custom_report<'a, 'b>(text: &'a mut String, filter: &str, columns: &[&str]) -> Result<TaskList<'a>, &'b str> {
+    let output = Command::new("task")
+        .arg(format!("rc.report.lazytask.columns={}", columns.join(",")))
+        .arg(format!("rc.report.lazytask.labels={}", columns.join(",")))
+        .arg("rc.report.lazytask.filter=")
+        .arg("rc.report.lazytask.sort=entry+")
+        .arg("rc.verbose=blank,label")
+        .arg("rc.detection=off")
+        .arg("rc.defaultwidth=0")
+        .args(filter.split_whitespace())
+        .arg("lazytask")
+        .output();
+
+    let stdout = match output {
+        Ok(o) => String::from_utf8(o.stdout).unwrap_or_default(),
+        Err(_) => return Err("Could not convert command output to UTF-8 string")
+    };
+
+    text.push_str(&stdout);
+
+    parse_task_list(text, false)
+}
+
+pub fn get_active_task_list<'a, 'b>(text: &'a mut String) -> Result<TaskList<'a>, &'b str> {
+    let output = Command::new("task")
+        .arg("rc.defaultwidth=0")
+        .arg("active")
+        .output();
+
+    let stdout = match output {
+        Ok(o) => String::from_utf8(o.stdout).unwrap_or_default(),
+        Err(_) => return Err("Could not convert command output to UTF-8 string")
+    };
+
+    text.push_str(&stdout);
+
+    parse_task_list(text, true)
+}
+
+/// Start of the interval Timewarrior is currently tracking, if any.
+pub fn get_active_start<'a>() -> Result<Option<DateTime<Local>>, &'a str> {
+    let output = Command::new("timew")
+        .arg("get")
+        .arg("dom.active.start")
+        .output()
+        .map_err(|_| "Could not run timew")?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let text = String::from_utf8(output.stdout).unwrap_or_default();
+    Ok(parse_timew_date(text.trim()))
+}
+
+/// Parses the local extended ISO dates of `timew get` and the UTC basic
+/// ISO dates of `timew export`.
+pub fn parse_timew_date(text: &str) -> Option<DateTime<Local>> {
+    if let Ok(date) = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S") {
+        return Local.from_local_datetime(&date).earliest();
+    }
+
+    NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|date| Utc.from_utc_datetime(&date).with_timezone(&Local))
 }
 
 pub fn get_interval_list<'a, 'b>(text: &'a mut String)-> Result<TaskList<'a>, &'b str> {
+    get_interval_list_for(text, "")
+}
+
+pub fn get_interval_list_for<'a, 'b>(text: &'a mut String, range: &str)-> Result<TaskList<'a>, &'b str> {
     let output = Command::new("timew")
         .arg("summary")
         .arg(":ids")
+        .args(range.split_whitespace())
         .output();
 
     let stdout = match output {
@@ -47,8 +247,85 @@ pub fn get_interval_list<'a, 'b>(text: &'a mut String)-> Result<TaskList<'a>, &'
 }
 
 pub fn get_task_list<'a, 'b>(text: &'a mut String) -> Result<TaskList<'a>, &'b str> {
+    get_task_report(text, "next", "")
+}
+
+pub fn get_task_report<'a, 'b>(text: &'a mut String, report: &str, filter: &str) -> Result<TaskList<'a>, &'b str> {
+    run_task_report(text, &[], report, filter)
+}
+
+/// How a report is configured, to run it with an extra column.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReportLayout {
+    pub columns: String,
+    pub labels: String
+}
+
+pub fn get_report_layout<'a>(report: &str) -> Result<ReportLayout, &'a str> {
+    let get = |setting: &str| -> Result<String, &'a str> {
+        let output = Command::new("task")
+            .args(["rc.verbose=nothing", "_get"])
+            .arg(format!("rc.report.{}.{}", report, setting))
+            .output()
+            .map_err(|_| "Could not read the report settings")?;
+        Ok(String::from_utf8(output.stdout).unwrap_or_default().trim().to_string())
+    };
+
+    let columns = get("columns")?;
+    if columns.is_empty() {
+        return Err("Unknown report");
+    }
+
+    Ok(ReportLayout { columns, labels: get("labels")? })
+}
+
+/// A report with the UUID, modification time and dependency IDs of each
+/// task.
+pub struct KeyedTaskList<'a> {
+    pub list: TaskList<'a>,
+    pub uuids: Vec<String>,
+    pub modified: Vec<String>,
+    pub depends: Vec<String>
+}
+
+/// Runs the report with the task UUIDs, modification times and
+/// dependencies in extra columns, which are taken off the list and
+/// returned alongside it.
+pub fn get_keyed_task_report<'a, 'b>(text: &'a mut String, report: &str, layout: &ReportLayout, filter: &str) -> Result<KeyedTaskList<'a>, &'b str> {
+    let overrides = [
+        format!("rc.report.{}.columns={},uuid,modified.epoch,depends.list", report, layout.columns),
+        format!("rc.report.{}.labels={},UUID,Modified,Depends", report, layout.labels)
+    ];
+    let mut list = run_task_report(text, &overrides, report, filter)?;
+    let depends = take_last_column(&mut list, "Depends");
+    let modified = take_last_column(&mut list, "Modified");
+    let uuids = take_last_column(&mut list, "UUID");
+
+    Ok(KeyedTaskList { list, uuids, modified, depends })
+}
+
+/// Removes the last column titled `title` and returns its cells.
+fn take_last_column(list: &mut TaskList, title: &str) -> Vec<String> {
+    let index = match list.columns.iter().rposition(|column| *column == title) {
+        Some(index) => index,
+        None => return vec![String::new(); list.rows.len()]
+    };
+
+    list.columns.remove(index);
+    list.colsizes.remove(index);
+    list.rows
+        .iter_mut()
+        .map(|row| if index < row.len() { row.remove(index).to_string() } else { String::new() })
+        .collect()
+}
+
+fn run_task_report<'a, 'b>(text: &'a mut String, overrides: &[String], report: &str, filter: &str) -> Result<TaskList<'a>, &'b str> {
+    // Without a terminal Taskwarrior wraps at 80 columns
     let output = Command::new("task")
-        .arg("next")
+        .arg("rc.defaultwidth=0")
+        .args(overrides)
+        .args(filter.split_whitespace())
+        .arg(report)
         .output();
 
     let stdout = match output {
@@ -61,65 +338,324 @@ pub fn get_task_list<'a, 'b>(text: &'a mut String) -> Result<TaskList<'a>, &'b s
     parse_task_list(text, true)
 }
 
-pub fn add_task<'a, 'b>(text: &'a str) -> Result<(), &'b str>{
+/// The output of a command that ran and succeeded. A refused command
+/// fails with what it printed on stderr, or `error` when it printed
+/// nothing, so callers never record a change that did not happen.
+fn succeeded<'a>(output: io::Result<Output>, error: &'a str) -> Result<Output, &'a str> {
+    let output = output.map_err(|_| error)?;
+    if output.status.success() {
+        return Ok(output);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        Err(error)
+    } else {
+        // Errors are borrowed strings throughout; refusals are rare
+        // enough to keep their message for the rest of the run
+        Err(Box::leak(stderr.into_boxed_str()))
+    }
+}
+
+/// Adds a task; `args` are attributes such as `due:tomorrow`.
+pub fn add_task<'a, 'b>(text: &'a str, args: &[&str]) -> Result<(), &'b str>{
     let output = Command::new("task")
         .arg("add")
         .arg(text)
+        .args(args)
         .output();
 
-    match output {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Could not add task")
-    }
+    succeeded(output, "Could not add task").map(|_| ())
 }
 
-pub fn start_task<'a, 'b>(text: &'a str) -> Result<(), &'b str>{
+/// Starts a task. Like the other task changes that may start or stop
+/// time tracking, it returns how many Timewarrior commands ran, each
+/// undone by one `timew undo`.
+pub fn start_task<'a, 'b>(text: &'a str) -> Result<usize, &'b str>{
     let output = Command::new("task")
         .arg("start")
         .arg(text)
         .output();
 
-    match output {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Could not start task")
+    succeeded(output, "Could not start task")?;
+    if timew_fallback() {
+        track_task(text)?;
+    }
+    Ok(timew_driven() as usize)
+}
+
+/// How starting a task treats the tasks that are already active.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ActivePolicy {
+    /// Only one task is active at a time.
+    Single,
+    /// Tasks are started alongside the active ones.
+    Multiple,
+    /// Ask before stopping the active tasks.
+    Confirm
+}
+
+impl ActivePolicy {
+    pub fn parse(name: &str) -> Option<ActivePolicy> {
+        match name.to_lowercase().as_str() {
+            "single" => Some(ActivePolicy::Single),
+            "multiple" => Some(ActivePolicy::Multiple),
+            "confirm" => Some(ActivePolicy::Confirm),
+            _ => None
+        }
+    }
+}
+
+/// What `toggle_task` changed, as task UUIDs.
+#[derive(Default)]
+pub struct Toggle {
+    pub started: Option<String>,
+    pub stopped: Vec<String>,
+    /// Still active task whose time tracking was restarted.
+    pub resumed: Option<String>,
+    /// Timewarrior commands that ran, through the hook or from here.
+    pub timew: usize
+}
+
+/// Active tasks other than `task_id`, which starting it would stop.
+pub fn other_active_tasks<'a>(task_id: &str) -> Result<Vec<String>, &'a str> {
+    let uuid = get_task_attribute(task_id, "uuid")?;
+
+    Ok(get_active_uuids()?
+        .into_iter()
+        .filter(|active| *active != uuid)
+        .collect())
+}
+
+/// Stops `task_id` if it is active, otherwise starts it, first stopping the
+/// other active tasks when `stop_others` is set.
+pub fn toggle_task<'a>(task_id: &str, stop_others: bool) -> Result<Toggle, &'a str> {
+    let uuid = get_task_attribute(task_id, "uuid")?;
+    if uuid.is_empty() {
+        return Err("No such task");
+    }
+
+    let active = get_active_uuids()?;
+    if active.contains(&uuid) {
+        // Only the tracked task leaves the others without an interval
+        let tracked = active.len() > 1 && is_tracking(&uuid)?;
+        let mut timew = stop_task(&uuid)?;
+        let resumed = active.into_iter().rfind(|a| *a != uuid).filter(|_| tracked);
+        if let Some(other) = &resumed {
+            track_task(other)?;
+            timew += 1;
+        }
+
+        return Ok(Toggle { started: None, stopped: vec![uuid], resumed, timew });
+    }
+
+    let mut stopped = Vec::new();
+    let mut timew = 0;
+    if stop_others {
+        for other in active {
+            timew += stop_task(&other)?;
+            stopped.push(other);
+        }
+    }
+
+    timew += start_task(&uuid)?;
+    Ok(Toggle { started: Some(uuid), stopped, resumed: None, timew })
+}
+
+/// The tags the on-modify hook gives an interval: the description, the
+/// project and the task tags.
+pub fn get_tracking_tags<'a>(task_id: &str) -> Result<Vec<String>, &'a str> {
+    let mut tags = vec![get_task_attribute(task_id, "description")?];
+
+    let project = get_task_attribute(task_id, "project")?;
+    if !project.is_empty() {
+        tags.push(project);
+    }
+
+    tags.extend(get_task_attribute(task_id, "tags")?
+        .split(',')
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string()));
+
+    Ok(tags)
+}
+
+/// Starts a Timewarrior interval tagged the way the hook would tag it.
+/// Used without the hook, and to point Timewarrior back at a task that is
+/// still active: it tracks one interval at a time, so stopping one of
+/// several active tasks would otherwise leave the rest untracked.
+pub fn track_task<'a>(task_id: &str) -> Result<(), &'a str> {
+    let output = Command::new("timew")
+        .arg("start")
+        .args(get_tracking_tags(task_id)?)
+        .output();
+
+    succeeded(output, "Could not start time tracking").map(|_| ())
+}
+
+/// Tags of the interval Timewarrior is tracking; empty when it is not.
+pub fn get_active_tags<'a>() -> Result<Vec<String>, &'a str> {
+    let get = |reference: &str| -> Result<Option<String>, &'a str> {
+        let output = Command::new("timew")
+            .arg("get")
+            .arg(reference)
+            .output()
+            .map_err(|_| "Could not run timew")?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout).unwrap_or_default().trim().to_string()))
+    };
+
+    let count: usize = match get("dom.active.tag.count")? {
+        Some(count) => count.parse().unwrap_or_default(),
+        None => return Ok(Vec::new())
+    };
+
+    (1..=count)
+        .map(|index| get(&format!("dom.active.tag.{}", index)).map(Option::unwrap_or_default))
+        .collect()
+}
+
+/// Whether the interval Timewarrior is tracking is the one of the task.
+pub fn is_tracking<'a>(task_id: &str) -> Result<bool, &'a str> {
+    let active = get_active_tags()?;
+    if active.is_empty() {
+        return Ok(false);
+    }
+
+    Ok(get_tracking_tags(task_id)?.iter().all(|tag| active.contains(tag)))
+}
+
+/// Stops whatever Timewarrior is tracking.
+pub fn untrack<'a>() -> Result<(), &'a str> {
+    let output = Command::new("timew")
+        .arg("stop")
+        .output();
+
+    succeeded(output, "Could not stop time tracking").map(|_| ())
+}
+
+/// Reads a single attribute of a task, e.g. `uuid` or `description`.
+pub fn get_task_attribute<'a>(task_id: &str, attribute: &str) -> Result<String, &'a str> {
+    let output = Command::new("task")
+        .arg("_get")
+        .arg(format!("{}.{}", task_id, attribute))
+        .output()
+        .map_err(|_| "Could not read task attribute")?;
+
+    Ok(String::from_utf8(output.stdout).unwrap_or_default().trim().to_string())
+}
+
+pub fn modify_task<'a>(task_id: &str, args: &[&str]) -> Result<(), &'a str> {
+    modify_task_series(task_id, args, false)
+}
+
+/// Modifies a task; for a recurring instance `series` also changes the
+/// other pending instances.
+pub fn modify_task_series<'a>(task_id: &str, args: &[&str], series: bool) -> Result<(), &'a str> {
+    let output = Command::new("task")
+        .arg("rc.confirmation:no")
+        .arg(recurrence_confirmation(series))
+        .arg(task_id)
+        .arg("modify")
+        .args(args)
+        .output();
+
+    let output = succeeded(output, "Could not modify task")?;
+    if String::from_utf8_lossy(&output.stdout).contains("Modified 0 tasks") {
+        return Err("Nothing to change");
     }
+    Ok(())
 }
 
-pub fn done_task<'a, 'b>(text: &'a str) -> Result<(), &'b str>{
+/// The tasks matching `filter` as `task export` JSON, one task per line.
+pub fn export_tasks<'a>(filter: &str) -> Result<String, &'a str> {
+    let output = Command::new("task")
+        .arg("rc.json.array=off")
+        .args(filter.split_whitespace())
+        .arg("export")
+        .output()
+        .map_err(|_| "Could not export tasks")?;
+
+    Ok(String::from_utf8(output.stdout).unwrap_or_default())
+}
+
+/// The full `task info` listing of a task.
+pub fn get_task_info<'a>(task_id: &str) -> Result<String, &'a str> {
+    let output = Command::new("task")
+        .arg("rc.detection=off")
+        .arg("rc.defaultwidth=80")
+        .arg(task_id)
+        .arg("info")
+        .output()
+        .map_err(|_| "Could not read task info")?;
+
+    Ok(String::from_utf8(output.stdout).unwrap_or_default())
+}
+
+fn recurrence_confirmation(series: bool) -> &'static str {
+    if series {
+        "rc.recurrence.confirmation:yes"
+    } else {
+        "rc.recurrence.confirmation:no"
+    }
+}
+
+pub fn annotate_task<'a>(task_id: &str, text: &str) -> Result<(), &'a str> {
+    let output = Command::new("task")
+        .arg(task_id)
+        .arg("annotate")
+        .arg(text)
+        .output();
+
+    succeeded(output, "Could not annotate task").map(|_| ())
+}
+
+pub fn done_task<'a, 'b>(text: &'a str) -> Result<usize, &'b str>{
+    let tracked = timew_driven() && is_tracking(text)?;
     let output = Command::new("task")
         .arg("done")
         .arg(text)
         .output();
 
-    match output {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Could not done task")
-    }
+    succeeded(output, "Could not done task")?;
+    stopped_tracking(tracked)
 }
 
-pub fn stop_task<'a, 'b>(text: &'a str) -> Result<(), &'b str>{
+pub fn stop_task<'a, 'b>(text: &'a str) -> Result<usize, &'b str>{
+    // Another active task may be the one Timewarrior tracks
+    let tracked = timew_driven() && is_tracking(text)?;
     let output = Command::new("task")
         .arg("stop")
         .arg(text)
         .output();
 
-    match output {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Could not stop task")
+    succeeded(output, "Could not stop task")?;
+    stopped_tracking(tracked)
+}
+
+/// Stops Timewarrior after a tracked task stopped, unless the hook did.
+fn stopped_tracking<'a>(tracked: bool) -> Result<usize, &'a str> {
+    if tracked && timew_fallback() {
+        untrack()?;
     }
+    Ok(tracked as usize)
 }
 
-pub fn delete_task<'a, 'b>(task_id: &'a str) -> Result<(), &'b str>{
+/// Deletes a task; for a recurring instance `series` also deletes the
+/// other pending instances.
+pub fn delete_task<'a, 'b>(task_id: &'a str, series: bool) -> Result<usize, &'b str>{
+    let tracked = timew_driven() && is_tracking(task_id)?;
     let output = Command::new("task")
         .arg("delete")
         .arg("rc.confirmation:no")
+        .arg(recurrence_confirmation(series))
         .arg(task_id)
         .output();
 
-    match output {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Could not delete task")
-    }
+    succeeded(output, "Could not delete task")?;
+    stopped_tracking(tracked)
 }
 
 pub fn delete_time<'a, 'b>(interval_id: &'a str) -> Result<(), &'b str>{
@@ -128,9 +664,52 @@ pub fn delete_time<'a, 'b>(interval_id: &'a str) -> Result<(), &'b str>{
         .arg(interval_id)
         .output();
 
-    match output {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Could not delete interval")
+    succeeded(output, "Could not delete interval").map(|_| ())
+}
+
+/// Reverts the most recent change to the task database.
+pub fn undo_task<'a>() -> Result<(), &'a str> {
+    let output = Command::new("task")
+        .arg("rc.confirmation:off")
+        .arg("undo")
+        .output()
+        .map_err(|_| "Could not run task undo")?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err("Taskwarrior has nothing to undo")
+    }
+}
+
+/// Reverts the most recent Timewarrior command.
+pub fn undo_time<'a>() -> Result<(), &'a str> {
+    let output = Command::new("timew")
+        .arg("undo")
+        .output()
+        .map_err(|_| "Could not run timew undo")?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err("Timewarrior has nothing to undo")
+    }
+}
+
+/// Moves the end of a closed interval, e.g. `modify_interval_end("@1", "18:00")`.
+pub fn modify_interval_end<'a>(interval_id: &str, end: &str) -> Result<(), &'a str> {
+    let output = Command::new("timew")
+        .arg("modify")
+        .arg("end")
+        .arg(interval_id)
+        .args(end.split_whitespace())
+        .output()
+        .map_err(|_| "Could not modify interval")?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err("Timewarrior refused to modify the interval")
     }
 }
 
@@ -159,26 +738,32 @@ fn parse_task_list<'a, 'b>(text: &'a str, sort: bool) -> Result<TaskList<'a>, &'
         .collect();
 
     if sort {
-        rows.sort_by(|a: &Vec<&str>, b: &Vec<&str>| {
-            let sa = *a.get(0).unwrap();
-            let sb = *b.get(0).unwrap();
-            let ia: u32 = sa.parse().unwrap();
-            let ib: u32 = sb.parse().unwrap();
-
-            ia.cmp(&ib)
-        });
+        // Annotations and wrapped descriptions continue on lines without
+        // an ID
+        rows.retain(|row: &Vec<&str>| row.first().is_some_and(|id| !id.is_empty()));
+        rows.sort_by_key(|row: &Vec<&str>| row[0].parse::<u32>().ok());
     }
 
     Ok(TaskList { colsizes, columns, rows })
 }
 
+/// Cuts a row into cells. The column sizes are terminal cells, as
+/// Taskwarrior pads by display width, so they are mapped back to byte
+/// offsets at grapheme boundaries.
 fn split_row<'a>(text: &'a str, colsizes: &Vec<usize>) -> Vec<&'a str> {
-    let max = text.len();
+    let mut offsets: Vec<usize> = Vec::with_capacity(text.len() + 1);
+    for (index, grapheme) in text.grapheme_indices(true) {
+        for _ in 0..grapheme.width() {
+            offsets.push(index);
+        }
+    }
+    let offset = |column: usize| offsets.get(column).copied().unwrap_or(text.len());
+
     let mut save: usize = 0;
     colsizes.iter()
         .map(|width| {
-            let start = cmp::min(max, save);
-            let end = cmp::min(max, start + width);
+            let start = offset(save);
+            let end = cmp::max(start, offset(save + width));
             save += width + 1;
             text[start..end].trim()
         })
@@ -222,6 +807,37 @@ ID Description
         assert_eq!(["3", "Bake cake"].to_vec(), *result.rows.get(2).unwrap());
     }
 
+    #[test]
+    fn test_parse_task_list_continuation_lines() {
+        let data = "
+ID Description
+-- -------------------
+2  Write the report
+     2020-09-03 draft
+1  Buy milk
+
+2 tasks.";
+
+        let result = parse_task_list(data, true).unwrap();
+        assert_eq!(vec![vec!["1", "Buy milk"], vec!["2", "Write the report"]], result.rows);
+    }
+
+    #[test]
+    fn test_take_last_column() {
+        let data = "
+ID Description UUID
+-- ----------- ----
+2  Buy eggs    b
+1  Buy milk    a
+
+2 tasks.";
+
+        let mut list = parse_task_list(data, true).unwrap();
+        assert_eq!(vec!["a", "b"], take_last_column(&mut list, "UUID"));
+        assert_eq!(vec!["ID", "Description"], list.columns);
+        assert_eq!(vec![vec!["1", "Buy milk"], vec!["2", "Buy eggs"]], list.rows);
+    }
+
     #[test]
     fn test_parse_task_list_2() {
         let data = "
@@ -246,6 +862,15 @@ W36 2020-09-03 Thu @6 Planning with GL and KS  9:00:00 10:00:00 1:00:00
         assert_eq!(6, result.rows.len());
     }
 
+    #[test]
+    fn test_split_row_wide_characters() {
+        let colsizes = vec![2, 6, 3];
+
+        assert_eq!(["1", "日本語", "H"].to_vec(), split_row("1  日本語 H", &colsizes));
+        assert_eq!(["2", "cafe\u{301}", "L"].to_vec(), split_row("2  cafe\u{301}   L", &colsizes));
+        assert_eq!(["3", "ab", ""].to_vec(), split_row("3  ab", &colsizes));
+    }
+
     #[test]
     fn test_parse_task_list_empty() {
         let data = "No matches.";
@@ -287,4 +912,15 @@ ID Description Age
         assert_eq!("Intercom adhoc", *x.get(2).unwrap());
         assert_eq!("0.38", *x.get(3).unwrap());
     }
+
+    #[test]
+    fn timew_dates() {
+        let local = parse_timew_date("2020-09-03T09:00:00").unwrap();
+        assert_eq!("2020-09-03 09:00:00", local.format("%Y-%m-%d %H:%M:%S").to_string());
+
+        let utc = parse_timew_date("20200903T090000Z").unwrap();
+        assert_eq!("2020-09-03 09:00:00", utc.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S").to_string());
+
+        assert_eq!(None, parse_timew_date("0"));
+    }
 }
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use crate::taskwarrior::{self, TaskList};
+
+pub struct Interval {
+    pub id: String,
+    pub date: String,
+    pub tags: String,
+    pub start: String,
+    pub end: String,
+    pub seconds: u64
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Csv,
+    Json,
+    Markdown
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Markdown => "md"
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RoundingMode {
+    Nearest,
+    Up,
+    Down
+}
+
+impl RoundingMode {
+    pub fn parse(name: &str) -> Option<RoundingMode> {
+        match name.to_lowercase().as_str() {
+            "nearest" => Some(RoundingMode::Nearest),
+            "up" => Some(RoundingMode::Up),
+            "down" => Some(RoundingMode::Down),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rounding {
+    pub minutes: u64,
+    pub mode: RoundingMode
+}
+
+impl Rounding {
+    pub fn none() -> Self {
+        Self { minutes: 0, mode: RoundingMode::Nearest }
+    }
+
+    pub fn apply(&self, seconds: u64) -> u64 {
+        if self.minutes == 0 {
+            return seconds;
+        }
+
+        let step = self.minutes * 60;
+        let steps = match self.mode {
+            RoundingMode::Nearest => (seconds + step / 2) / step,
+            RoundingMode::Up => seconds.div_ceil(step),
+            RoundingMode::Down => seconds / step
+        };
+
+        steps * step
+    }
+}
+
+/// Renders the intervals of a Timewarrior range, or their per-tag totals.
+pub fn export<'a>(range: &str, format: Format, rounding: &Rounding, totals: bool) -> Result<String, &'a str> {
+    let mut text = String::new();
+    let list = taskwarrior::get_interval_list_for(&mut text, range)?;
+    let intervals = intervals_from_list(&list);
+
+    if totals {
+        Ok(render_totals(format, &aggregate(&intervals), rounding))
+    } else {
+        Ok(render_intervals(format, &intervals, rounding))
+    }
+}
+
+/// Builds intervals from a `timew summary :ids` listing. Only the first
+/// interval of a day carries the date, so it is copied down to the rest.
+pub fn intervals_from_list(list: &TaskList) -> Vec<Interval> {
+    let mut current_date = "";
+    list.rows
+        .iter()
+        .filter(|row| !list.cell(row, "Time").is_empty())
+        .map(|row| {
+            let row_date = list.cell(row, "Date");
+            if !row_date.is_empty() {
+                current_date = row_date;
+            }
+
+            Interval {
+                id: list.cell(row, "ID").to_string(),
+                date: current_date.to_string(),
+                tags: list.cell(row, "Tags").to_string(),
+                start: list.cell(row, "Start").to_string(),
+                end: list.cell(row, "End").to_string(),
+                seconds: parse_duration(list.cell(row, "Time")).unwrap_or_default()
+            }
+        })
+        .collect()
+}
+
+/// Sums the tracked time per tag set, keeping the tags in sorted order.
+pub fn aggregate(intervals: &[Interval]) -> Vec<(String, u64)> {
+    let mut totals: BTreeMap<&str, u64> = BTreeMap::new();
+    for interval in intervals {
+        *totals.entry(&interval.tags).or_insert(0) += interval.seconds;
+    }
+
+    totals
+        .into_iter()
+        .map(|(tags, seconds)| (tags.to_string(), seconds))
+        .collect()
+}
+
+pub fn render_intervals(format: Format, intervals: &[Interval], rounding: &Rounding) -> String {
+    let headers = ["id", "date", "tags", "start", "end", "duration", "hours"];
+    let rows: Vec<Vec<String>> = intervals
+        .iter()
+        .map(|i| {
+            let seconds = rounding.apply(i.seconds);
+            vec![
+                i.id.clone(),
+                i.date.clone(),
+                i.tags.clone(),
+                i.start.clone(),
+                i.end.clone(),
+                format_duration(seconds),
+                format_hours(seconds)
+            ]
+        })
+        .collect();
+
+    render(format, &headers, &rows)
+}
+
+pub fn render_totals(format: Format, totals: &[(String, u64)], rounding: &Rounding) -> String {
+    let headers = ["tags", "duration", "hours"];
+    let rows: Vec<Vec<String>> = totals
+        .iter()
+        .map(|(tags, seconds)| {
+            let seconds = rounding.apply(*seconds);
+            vec![tags.clone(), format_duration(seconds), format_hours(seconds)]
+        })
+        .collect();
+
+    render(format, &headers, &rows)
+}
+
+fn render(format: Format, headers: &[&str], rows: &[Vec<String>]) -> String {
+    match format {
+        Format::Csv => render_csv(headers, rows),
+        Format::Json => render_json(headers, rows),
+        Format::Markdown => render_markdown(headers, rows)
+    }
+}
+
+fn render_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let escape = |value: &str| {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut out = headers.join(",");
+    out.push('\n');
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| escape(c)).collect();
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn render_json(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let objects: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<String> = headers
+                .iter()
+                .zip(row)
+                .map(|(h, c)| format!("{}:{}", json_string(h), json_string(c)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        })
+        .collect();
+
+    format!("[{}]\n", objects.join(","))
+}
+
+fn render_markdown(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let escape = |value: &str| value.replace('|', "\\|");
+
+    let mut out = format!("| {} |\n", headers.join(" | "));
+    let dashes: Vec<&str> = headers.iter().map(|_| "---").collect();
+    out.push_str(&format!("|{}|\n", dashes.join("|")));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| escape(c)).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+
+    out
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+
+    out
+}
+
+/// Parses `H:MM:SS` as printed by Timewarrior.
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let parts: Vec<u64> = text
+        .trim()
+        .split(':')
+        .map(|p| p.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    match parts.as_slice() {
+        [h, m, s] => Some(h * 3600 + m * 60 + s),
+        [m, s] => Some(m * 60 + s),
+        _ => None
+    }
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+pub fn format_hours(seconds: u64) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> TaskList<'static> {
+        TaskList {
+            colsizes: vec![3, 10, 3, 2, 23, 8, 8, 7, 7],
+            columns: vec!["Wk", "Date", "Day", "ID", "Tags", "Start", "End", "Time", "Total"],
+            rows: vec![
+                vec!["W36", "2020-09-03", "Thu", "@3", "Planning", "9:00:00", "10:00:00", "1:00:00", ""],
+                vec!["", "", "", "@2", "code reviews", "10:14:59", "11:30:00", "1:15:01", ""],
+                vec!["", "", "", "@1", "Planning", "12:00:00", "12:07:00", "0:07:00", "2:22:01"]
+            ]
+        }
+    }
+
+    #[test]
+    fn intervals_carry_date() {
+        let intervals = intervals_from_list(&summary());
+
+        assert_eq!(3, intervals.len());
+        assert_eq!("2020-09-03", intervals[2].date);
+        assert_eq!("@2", intervals[1].id);
+        assert_eq!(4501, intervals[1].seconds);
+    }
+
+    #[test]
+    fn aggregate_by_tags() {
+        let totals = aggregate(&intervals_from_list(&summary()));
+
+        assert_eq!(vec![("Planning".to_string(), 4020), ("code reviews".to_string(), 4501)], totals);
+    }
+
+    #[test]
+    fn rounding() {
+        let up = Rounding { minutes: 15, mode: RoundingMode::Up };
+        let down = Rounding { minutes: 15, mode: RoundingMode::Down };
+        let nearest = Rounding { minutes: 15, mode: RoundingMode::Nearest };
+
+        assert_eq!(900, up.apply(60));
+        assert_eq!(0, down.apply(899));
+        assert_eq!(900, nearest.apply(450));
+        assert_eq!(0, nearest.apply(449));
+        assert_eq!(449, Rounding::none().apply(449));
+    }
+
+    #[test]
+    fn render_formats() {
+        let totals = vec![("a, \"b\"".to_string(), 5400)];
+
+        assert_eq!("tags,duration,hours\n\"a, \"\"b\"\"\",1:30:00,1.50\n",
+            render_totals(Format::Csv, &totals, &Rounding::none()));
+        assert_eq!("[{\"tags\":\"a, \\\"b\\\"\",\"duration\":\"1:30:00\",\"hours\":\"1.50\"}]\n",
+            render_totals(Format::Json, &totals, &Rounding::none()));
+        assert_eq!("| tags | duration | hours |\n|---|---|---|\n| a, \"b\" | 1:30:00 | 1.50 |\n",
+            render_totals(Format::Markdown, &totals, &Rounding::none()));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(Some(4501), parse_duration("1:15:01"));
+        assert_eq!(None, parse_duration("-"));
+        assert_eq!("12:05:09", format_duration(43509));
+    }
+}
@@ -0,0 +1,105 @@
+use std::rc::Rc;
+
+use cursive::Cursive;
+use cursive::views::{Dialog, OnEventView};
+
+use crate::state::AppState;
+use crate::taskwarrior::{self, ActivePolicy, Toggle};
+use crate::undo;
+
+/// Starts or stops the task the way the active-task policy says, asking
+/// first when it says so, then calls `after` with what changed.
+pub fn toggle<F>(s: &mut Cursive, task_id: &str, after: F)
+where
+    F: Fn(&mut Cursive, &Toggle) + 'static
+{
+    let policy = s.user_data::<AppState>().expect("App state").config.active_policy;
+    match policy {
+        ActivePolicy::Single => apply(s, task_id, true, &after),
+        ActivePolicy::Multiple => apply(s, task_id, false, &after),
+        ActivePolicy::Confirm => {
+            let others = if is_tracked(task_id) {
+                Vec::new()
+            } else {
+                match taskwarrior::other_active_tasks(task_id) {
+                    Ok(others) => others,
+                    Err(e) => return s.add_layer(Dialog::info(e))
+                }
+            };
+
+            if others.is_empty() {
+                apply(s, task_id, false, &after);
+                return;
+            }
+
+            let names: Vec<String> = others
+                .iter()
+                .map(|uuid| format!("  {}", taskwarrior::get_task_attribute(uuid, "description").unwrap_or_default()))
+                .collect();
+            let after = Rc::new(after);
+            let keep_after = after.clone();
+            let task_id = task_id.to_string();
+            let keep_id = task_id.clone();
+            s.add_layer(OnEventView::new(
+                Dialog::text(format!("Stop the other active tasks?\n{}", names.join("\n")))
+                    .button("Stop them", move |s| {
+                        s.pop_layer();
+                        apply(s, &task_id, true, after.as_ref());
+                    })
+                    .button("Keep them", move |s| {
+                        s.pop_layer();
+                        apply(s, &keep_id, false, keep_after.as_ref());
+                    })
+                    .dismiss_button("Cancel"))
+                .on_event(cursive::event::Key::Esc, |s| {
+                    s.pop_layer();
+                }));
+        }
+    }
+}
+
+/// Stops the task if it is active; does nothing otherwise.
+pub fn stop<F>(s: &mut Cursive, task_id: &str, after: F)
+where
+    F: Fn(&mut Cursive, &Toggle) + 'static
+{
+    if is_tracked(task_id) {
+        toggle(s, task_id, after);
+    }
+}
+
+/// Whether changing the task also starts or stops time tracking.
+pub fn is_tracked(task_id: &str) -> bool {
+    taskwarrior::get_task_attribute(task_id, "start")
+        .map(|start| !start.is_empty())
+        .unwrap_or_default()
+}
+
+/// Toggles the task, records how to undo it and tells which tasks were
+/// started and stopped.
+fn apply<F>(s: &mut Cursive, task_id: &str, stop_others: bool, after: &F)
+where
+    F: Fn(&mut Cursive, &Toggle) + ?Sized
+{
+    let toggle = match taskwarrior::toggle_task(task_id, stop_others) {
+        Ok(toggle) => toggle,
+        Err(e) => return s.add_layer(Dialog::info(e))
+    };
+
+    let describe = |uuid: &String| taskwarrior::get_task_attribute(uuid, "description").unwrap_or_default();
+    let mut changes: Vec<String> = toggle.stopped
+        .iter()
+        .map(|uuid| format!("stopped \"{}\"", describe(uuid)))
+        .collect();
+    if let Some(uuid) = &toggle.started {
+        changes.push(format!("started \"{}\"", describe(uuid)));
+    }
+    let message = changes.join(", ");
+
+    let mut inverse = vec![undo::Inverse::TaskUndo; toggle.stopped.len() + toggle.started.iter().count()];
+    inverse.extend(vec![undo::Inverse::TimewUndo; toggle.timew]);
+
+    undo::record(s, message.clone(), inverse);
+    s.user_data::<AppState>().expect("App state").notify(message);
+    after(s, &toggle);
+}
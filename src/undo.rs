@@ -0,0 +1,117 @@
+use cursive::Cursive;
+
+use crate::state::AppState;
+use crate::taskwarrior;
+
+const LIMIT: usize = 50;
+
+/// One step that reverses part of an action.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Inverse {
+    /// `task undo`, for the last change lazytask made to a task.
+    TaskUndo,
+    /// `timew undo`, for intervals written by lazytask or the on-modify hook.
+    TimewUndo,
+    /// `task <uuid> modify <args>`, when the change is known precisely.
+    Modify(String, Vec<String>)
+}
+
+pub struct Action {
+    pub description: String,
+    pub inverse: Vec<Inverse>
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    actions: Vec<Action>
+}
+
+impl UndoStack {
+    pub fn push(&mut self, action: Action) {
+        if action.inverse.is_empty() {
+            return;
+        }
+
+        self.actions.push(action);
+        if self.actions.len() > LIMIT {
+            self.actions.remove(0);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Action> {
+        self.actions.pop()
+    }
+}
+
+/// Inverse of a task change, which also needs a `timew undo` for each of
+/// the `timew` Timewarrior commands it ran.
+pub fn task_change(timew: usize) -> Vec<Inverse> {
+    let mut inverse = vec![Inverse::TaskUndo];
+    inverse.extend(vec![Inverse::TimewUndo; timew]);
+
+    inverse
+}
+
+pub fn record<S: Into<String>>(s: &mut Cursive, description: S, inverse: Vec<Inverse>) {
+    s.user_data::<AppState>().expect("App state").undo.push(Action {
+        description: description.into(),
+        inverse
+    });
+}
+
+/// Reverts the most recent action and reports it on the status line.
+pub fn undo_last(s: &mut Cursive) {
+    let state = s.user_data::<AppState>().expect("App state");
+    let action = match state.undo.pop() {
+        Some(action) => action,
+        None => {
+            state.notify("Nothing to undo");
+            return;
+        }
+    };
+
+    // Steps were recorded in the order the changes happened
+    let result = action.inverse
+        .iter()
+        .rev()
+        .try_for_each(|inverse| match inverse {
+            Inverse::TaskUndo => taskwarrior::undo_task(),
+            Inverse::TimewUndo => taskwarrior::undo_time(),
+            Inverse::Modify(uuid, args) => {
+                let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+                taskwarrior::modify_task(uuid, &args)
+            }
+        });
+
+    match result {
+        Ok(()) => state.notify(format!("Undone: {}", action.description)),
+        Err(e) => state.notify(format!("Undo failed: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(description: &str) -> Action {
+        Action { description: description.to_string(), inverse: vec![Inverse::TaskUndo] }
+    }
+
+    #[test]
+    fn stack_order_and_limit() {
+        let mut stack = UndoStack::default();
+        for i in 0..LIMIT + 5 {
+            stack.push(action(&i.to_string()));
+        }
+        stack.push(Action { description: "noop".to_string(), inverse: Vec::new() });
+
+        assert_eq!((LIMIT + 4).to_string(), stack.pop().unwrap().description);
+        assert_eq!(LIMIT - 1, std::iter::from_fn(|| stack.pop()).count());
+    }
+
+    #[test]
+    fn tracked_changes() {
+        assert_eq!(vec![Inverse::TaskUndo], task_change(0));
+        assert_eq!(vec![Inverse::TaskUndo, Inverse::TimewUndo], task_change(1));
+    }
+}